@@ -1,6 +1,32 @@
 extern crate pokers;
 
+use std::env;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use pokers::config::{parse_args, USAGE};
 use pokers::game::play_game;
+
 fn main() {
-    play_game(6, false, 100, 1, 2, None);
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let config = match parse_args(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if let Err(e) = play_game(&config, started_at) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
 }
@@ -1,9 +1,12 @@
 use std::fmt;
 use std::str::FromStr;
 use std::cmp::Ordering;
-use itertools::Itertools;
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Rank {
     Two,
     Three,
@@ -107,7 +110,8 @@ impl FromStr for Rank {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Suit {
     Spades,
     Hearts,
@@ -122,6 +126,15 @@ pub fn suits() -> [Suit; 4] {
 }
 
 impl Suit {
+    pub fn val(self) -> u32 {
+        match self {
+            Suit::Spades => 0,
+            Suit::Hearts => 1,
+            Suit::Diamonds => 2,
+            Suit::Clubs => 3,
+        }
+    }
+
     pub fn to_unicode_string(self) -> &'static str {
         match self {
             Suit::Spades => "\u{2660}",
@@ -159,7 +172,7 @@ impl FromStr for Suit {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Card {
     pub rank: Rank,
     pub suit: Suit,
@@ -167,6 +180,30 @@ pub struct Card {
 
 pub type CardVec = Vec<Card>;
 
+impl Card {
+    // Position in a fresh deck: suits in `suits()` order, ranks ascending within each suit
+    pub fn index(self) -> u32 {
+        self.suit.val() * 13 + self.rank.val()
+    }
+
+    // Inverse of the short form `FromStr`, e.g. "As"
+    pub fn to_short_string(self) -> String {
+        format!(
+            "{}{}",
+            self.rank.to_string(),
+            self.suit.to_ascii_string().to_lowercase()
+        )
+    }
+
+    pub fn from_index(index: u32) -> Card {
+        assert!(index < 52, "Card index out of range: {}", index);
+        Card {
+            rank: RANKS[(index % 13) as usize],
+            suit: SUITS[(index / 13) as usize],
+        }
+    }
+}
+
 impl Ord for Card {
     fn cmp(&self, other: &Card) -> Ordering {
         self.rank.cmp(&other.rank)
@@ -199,6 +236,86 @@ impl fmt::Debug for Card {
         )
     }
 }
+#[derive(Debug, Fail)]
+pub enum CardParseError {
+    #[fail(display = "Invalid card: {}", _0)]
+    InvalidCard(String),
+    #[fail(display = "Duplicate card: {}", _0)]
+    DuplicateCard(String),
+}
+
+// Short form cards: a rank followed by a suit letter, e.g. "As", "Td" or "10h"
+impl FromStr for Card {
+    type Err = CardParseError;
+
+    fn from_str(card: &str) -> Result<Self, CardParseError> {
+        let card = card.trim();
+        let invalid = || CardParseError::InvalidCard(card.to_string());
+        let suit_start = card.char_indices().last().ok_or_else(invalid)?.0;
+        let (rank, suit) = card.split_at(suit_start);
+
+        Ok(Card {
+            rank: Rank::from_str(rank).map_err(|_| invalid())?,
+            suit: Suit::from_str(suit).map_err(|_| invalid())?,
+        })
+    }
+}
+
+// Cards serialize in their short form, e.g. "As"
+#[cfg(feature = "serde")]
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_short_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let card = String::deserialize(deserializer)?;
+        Card::from_str(&card).map_err(de::Error::custom)
+    }
+}
+
+// Parses a run of short form cards such as "AsKh 7d7c2s"; whitespace and commas between cards are
+// ignored. Each card may only appear once.
+pub fn parse_cards(cards: &str) -> Result<CardVec, CardParseError> {
+    let chars: Vec<char> = cards
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .collect();
+    let mut parsed: CardVec = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let rank_len = if chars[i] == '1' && chars.get(i + 1) == Some(&'0') {
+            2
+        } else {
+            1
+        };
+        let end = (i + rank_len + 1).min(chars.len());
+        let token: String = chars[i..end].iter().collect();
+        let card = Card::from_str(&token)?;
+
+        if parsed.contains(&card) {
+            return Err(CardParseError::DuplicateCard(token));
+        }
+        parsed.push(card);
+        i = end;
+    }
+
+    Ok(parsed)
+}
+
+// The other way round from parse_cards, separated by spaces, e.g. "As Kh 7d"
+pub fn cards_to_short_string(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|c| c.to_short_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
 pub fn card_from_str(rank: &str, suit: &str) -> Card {
     Card {
         rank: Rank::from_str(rank).unwrap(),
@@ -218,10 +335,92 @@ mod tests {
         assert_eq!(Suit::Hearts, c.suit);
     }
 
+    #[test]
+    fn it_round_trips_card_indices() {
+        for i in 0..52 {
+            assert_eq!(Card::from_index(i).index(), i);
+        }
+        assert_eq!(card_from_str("2", "S").index(), 0);
+        assert_eq!(card_from_str("A", "C").index(), 51);
+    }
+
+    #[test]
+    fn it_parses_short_form_cards() {
+        assert_eq!(Card::from_str("As").unwrap(), card_from_str("A", "S"));
+        assert_eq!(Card::from_str("Td").unwrap(), card_from_str("T", "D"));
+        assert_eq!(Card::from_str("10h").unwrap(), card_from_str("T", "H"));
+        assert_eq!(Card::from_str("7C").unwrap(), card_from_str("7", "C"));
+    }
+
+    #[test]
+    fn it_rejects_malformed_cards() {
+        assert!(Card::from_str("").is_err());
+        assert!(Card::from_str("A").is_err());
+        assert!(Card::from_str("Ax").is_err());
+        assert!(Card::from_str("1s").is_err());
+    }
+
+    #[test]
+    fn it_parses_card_runs() {
+        let cards = parse_cards("AsKh 7d7c2s").unwrap();
+
+        assert_eq!(
+            cards,
+            vec![
+                card_from_str("A", "S"),
+                card_from_str("K", "H"),
+                card_from_str("7", "D"),
+                card_from_str("7", "C"),
+                card_from_str("2", "S"),
+            ]
+        );
+        assert_eq!(parse_cards("10s, 9s").unwrap().len(), 2);
+        assert_eq!(parse_cards("").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn it_round_trips_short_form_cards() {
+        for i in 0..52 {
+            let card = Card::from_index(i);
+            assert_eq!(Card::from_str(&card.to_short_string()).unwrap(), card);
+        }
+        assert_eq!(card_from_str("T", "D").to_short_string(), "Td");
+
+        let cards = parse_cards("AsKh 7d").unwrap();
+        assert_eq!(cards_to_short_string(&cards), "As Kh 7d");
+        assert_eq!(parse_cards(&cards_to_short_string(&cards)).unwrap(), cards);
+    }
+
+    #[test]
+    fn it_rejects_bad_card_runs() {
+        match parse_cards("AsKhAs") {
+            Err(CardParseError::DuplicateCard(card)) => assert_eq!(card, "As"),
+            other => panic!("Expected a duplicate card error, got {:?}", other),
+        }
+        match parse_cards("AsK") {
+            Err(CardParseError::InvalidCard(card)) => assert_eq!(card, "K"),
+            other => panic!("Expected an invalid card error, got {:?}", other),
+        }
+    }
+
     #[test]
     fn it_formats_cards() {
         let ace_of_spades = card_from_str("A", "S");
 
         assert_eq!("A of ♠", format!("{}", ace_of_spades));
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_cards_in_short_form() {
+        let ace_of_spades = card_from_str("A", "S");
+        let json = ::serde_json::to_string(&ace_of_spades).unwrap();
+
+        assert_eq!("\"As\"", json);
+        assert_eq!(
+            ace_of_spades,
+            ::serde_json::from_str::<Card>(&json).unwrap()
+        );
+        assert!(::serde_json::from_str::<Card>("\"Zz\"").is_err());
+    }
 }
@@ -0,0 +1,264 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, Not, Sub};
+use std::str::FromStr;
+
+use card::{parse_cards, Card, CardParseError, CardVec, Suit};
+
+const FULL_DECK: u64 = (1 << 52) - 1;
+const SUIT_MASK: u64 = (1 << 13) - 1;
+
+// A set of cards as a 52 bit mask, one bit per `Card::index`
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub fn empty() -> CardSet {
+        CardSet(0)
+    }
+
+    pub fn full() -> CardSet {
+        CardSet(FULL_DECK)
+    }
+
+    pub fn from_bits(bits: u64) -> CardSet {
+        CardSet(bits & FULL_DECK)
+    }
+
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    // Returns false if the card was already present
+    pub fn insert(&mut self, card: Card) -> bool {
+        let present = self.contains(card);
+        self.0 |= bit(card);
+        !present
+    }
+
+    // Returns false if the card was not present
+    pub fn remove(&mut self, card: Card) -> bool {
+        let present = self.contains(card);
+        self.0 &= !bit(card);
+        present
+    }
+
+    pub fn contains(self, card: Card) -> bool {
+        self.0 & bit(card) != 0
+    }
+
+    pub fn union(self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+
+    pub fn difference(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    pub fn is_disjoint(self, other: CardSet) -> bool {
+        self.0 & other.0 == 0
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // Ranks held in the given suit, as a 13 bit mask indexed by `Rank::val`
+    pub fn suit_mask(self, suit: Suit) -> u16 {
+        ((self.0 >> (suit.val() * 13)) & SUIT_MASK) as u16
+    }
+
+    pub fn iter(self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+
+    pub fn to_vec(self) -> CardVec {
+        self.iter().collect()
+    }
+}
+
+fn bit(card: Card) -> u64 {
+    1 << card.index()
+}
+
+// Yields cards in `Card::index` order, i.e. the order of a fresh deck
+pub struct CardSetIter(u64);
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            None
+        } else {
+            let index = self.0.trailing_zeros();
+            self.0 &= self.0 - 1;
+            Some(Card::from_index(index))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> CardSet {
+        let mut set = CardSet::empty();
+        for card in iter {
+            set.insert(card);
+        }
+        set
+    }
+}
+
+impl<'a> From<&'a [Card]> for CardSet {
+    fn from(cards: &'a [Card]) -> CardSet {
+        cards.iter().cloned().collect()
+    }
+}
+
+impl<'a> From<&'a CardVec> for CardSet {
+    fn from(cards: &'a CardVec) -> CardSet {
+        cards.iter().cloned().collect()
+    }
+}
+
+impl From<CardSet> for CardVec {
+    fn from(set: CardSet) -> CardVec {
+        set.to_vec()
+    }
+}
+
+impl FromStr for CardSet {
+    type Err = CardParseError;
+
+    fn from_str(cards: &str) -> Result<Self, CardParseError> {
+        parse_cards(cards).map(|cards| CardSet::from(&cards))
+    }
+}
+
+impl BitOr for CardSet {
+    type Output = CardSet;
+
+    fn bitor(self, other: CardSet) -> CardSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for CardSet {
+    type Output = CardSet;
+
+    fn bitand(self, other: CardSet) -> CardSet {
+        self.intersection(other)
+    }
+}
+
+impl Sub for CardSet {
+    type Output = CardSet;
+
+    fn sub(self, other: CardSet) -> CardSet {
+        self.difference(other)
+    }
+}
+
+impl Not for CardSet {
+    type Output = CardSet;
+
+    fn not(self) -> CardSet {
+        CardSet(!self.0 & FULL_DECK)
+    }
+}
+
+impl fmt::Debug for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::card_from_str;
+    use test_utils::*;
+
+    #[test]
+    fn it_inserts_and_removes_cards() {
+        let mut set = CardSet::empty();
+        let ace = card_from_str("A", "S");
+
+        assert!(set.insert(ace));
+        assert!(!set.insert(ace));
+        assert!(set.contains(ace));
+        assert_eq!(set.count(), 1);
+        assert!(set.remove(ace));
+        assert!(!set.remove(ace));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn it_converts_from_and_to_card_vecs() {
+        let cards = flush_hand();
+        let set = CardSet::from(&cards);
+
+        assert_eq!(set.count(), 5);
+        assert!(cards.iter().all(|c| set.contains(*c)));
+
+        let mut sorted = cards.clone();
+        sorted.sort_by_key(|c| c.index());
+        assert_eq!(set.to_vec(), sorted);
+    }
+
+    #[test]
+    fn it_combines_sets() {
+        let a = CardSet::from(&vec![card_from_str("A", "S"), card_from_str("K", "S")]);
+        let b = CardSet::from(&vec![card_from_str("K", "S"), card_from_str("Q", "S")]);
+
+        assert_eq!((a | b).count(), 3);
+        assert_eq!(a & b, CardSet::from(&vec![card_from_str("K", "S")]));
+        assert_eq!(a - b, CardSet::from(&vec![card_from_str("A", "S")]));
+        assert!(!a.is_disjoint(b));
+        assert_eq!((!a).count(), 50);
+        assert_eq!(CardSet::full().count(), 52);
+    }
+
+    #[test]
+    fn it_parses_card_sets() {
+        let set = CardSet::from_str("AsKh 7d").unwrap();
+
+        assert_eq!(set.count(), 3);
+        assert!(set.contains(card_from_str("K", "H")));
+        assert!(CardSet::from_str("AsAs").is_err());
+    }
+
+    #[test]
+    fn it_reports_suit_masks() {
+        let set = CardSet::from(&vec![
+            card_from_str("2", "H"),
+            card_from_str("A", "H"),
+            card_from_str("A", "C"),
+        ]);
+
+        assert_eq!(set.suit_mask(Suit::Hearts), 1 | 1 << 12);
+        assert_eq!(set.suit_mask(Suit::Clubs), 1 << 12);
+        assert_eq!(set.suit_mask(Suit::Spades), 0);
+    }
+}
@@ -0,0 +1,362 @@
+use std::fs;
+use std::str::FromStr;
+
+use gamestate::{AnteMode, BettingStructure, DEFAULT_RAISE_CAP, MAX_PLAYERS, MIN_PLAYERS};
+
+// Settings for a game of the cli binary, read from a config file and/or command-line flags
+
+pub const USAGE: &str = "Usage: cli [options]
+
+Options:
+    --players N        Seats at the table, 2-10 (default 6)
+    --humans N         Seats played from the terminal (default 1)
+    --stack N          Starting stack for every seat (default 100)
+    --sb N             Small blind (default 1)
+    --bb N             Big blind (default 2)
+    --ante N           Ante, or 0 for none (default 0)
+    --ante-mode MODE   Who pays the ante: per-player or big-blind
+                       (default per-player)
+    --seed N           Seed for the game's randomness (default random)
+    --variant NAME     Game variant: holdem (default holdem)
+    --structure NAME   Betting structure: no-limit, pot-limit or fixed-limit
+                       (default no-limit)
+    --history FILE     Append a PokerStars-format history of every hand to FILE
+    --save FILE        Save the session to FILE after every hand
+    --resume FILE      Pick up a session saved with --save, which keeps saving
+                       to FILE unless --save says otherwise; the table comes
+                       from the save, so the settings above are ignored
+    --config FILE      Read settings from FILE, one `key = value` per line;
+                       flags given on the command line override it
+    -h, --help         Show this message";
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum ConfigError {
+    #[fail(display = "Unknown option: {}", _0)]
+    UnknownOption(String),
+    #[fail(display = "Missing value for {}", _0)]
+    MissingValue(String),
+    #[fail(display = "Invalid value for {}: {}", _0, _1)]
+    InvalidValue(String, String),
+    #[fail(display = "Couldn't read config file {}: {}", _0, _1)]
+    UnreadableFile(String, String),
+    #[fail(
+        display = "Config file line {}: expected `key = value`, got {}",
+        _0, _1
+    )]
+    MalformedLine(usize, String),
+    #[fail(display = "Need between {} and {} players, got {}", _0, _1, _2)]
+    PlayerCount(usize, usize, u32),
+    #[fail(display = "Can't seat {} humans at a table of {}", _0, _1)]
+    TooManyHumans(u32, u32),
+    #[fail(display = "Starting stack must be more than 0")]
+    EmptyStack,
+    #[fail(display = "Blinds must satisfy 0 < sb <= bb, got {}/{}", _0, _1)]
+    InvalidBlinds(u32, u32),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GameVariant {
+    Holdem,
+}
+
+impl FromStr for GameVariant {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<GameVariant, ()> {
+        match s.to_lowercase().as_str() {
+            "holdem" | "hold'em" | "texas-holdem" => Ok(GameVariant::Holdem),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for AnteMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<AnteMode, ()> {
+        match s.to_lowercase().as_str() {
+            "per-player" | "player" => Ok(AnteMode::PerPlayer),
+            "big-blind" | "bba" => Ok(AnteMode::BigBlind),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for BettingStructure {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<BettingStructure, ()> {
+        match s.to_lowercase().as_str() {
+            "no-limit" | "nolimit" | "nl" => Ok(BettingStructure::NoLimit),
+            "pot-limit" | "potlimit" | "pl" => Ok(BettingStructure::PotLimit),
+            "fixed-limit" | "limit" | "fl" => Ok(BettingStructure::FixedLimit {
+                cap: DEFAULT_RAISE_CAP,
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameConfig {
+    pub num_players: u32,
+    pub humans: u32,
+    pub starting_stack: u32,
+    pub sb: u32,
+    pub bb: u32,
+    pub ante: Option<u32>,
+    pub ante_mode: AnteMode,
+    pub seed: Option<u64>,
+    pub variant: GameVariant,
+    pub structure: BettingStructure,
+    pub history: Option<String>,
+    pub save: Option<String>,
+    pub resume: Option<String>,
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            num_players: 6,
+            humans: 1,
+            starting_stack: 100,
+            sb: 1,
+            bb: 2,
+            ante: None,
+            ante_mode: AnteMode::PerPlayer,
+            seed: None,
+            variant: GameVariant::Holdem,
+            structure: BettingStructure::NoLimit,
+            history: None,
+            save: None,
+            resume: None,
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T, ConfigError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ConfigError::InvalidValue(key.to_string(), value.to_string()))
+}
+
+impl GameConfig {
+    // Keys are the long option names without the dashes
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        match key {
+            "players" => self.num_players = parse_value(key, value)?,
+            "humans" => self.humans = parse_value(key, value)?,
+            "stack" => self.starting_stack = parse_value(key, value)?,
+            "sb" => self.sb = parse_value(key, value)?,
+            "bb" => self.bb = parse_value(key, value)?,
+            "ante" => {
+                let ante: u32 = parse_value(key, value)?;
+                self.ante = if ante > 0 { Some(ante) } else { None };
+            }
+            "ante-mode" => self.ante_mode = parse_value(key, value)?,
+            "seed" => self.seed = Some(parse_value(key, value)?),
+            "variant" => self.variant = parse_value(key, value)?,
+            "structure" => self.structure = parse_value(key, value)?,
+            "history" => self.history = Some(value.trim().to_string()),
+            "save" => self.save = Some(value.trim().to_string()),
+            "resume" => self.resume = Some(value.trim().to_string()),
+            _ => return Err(ConfigError::UnknownOption(key.to_string())),
+        }
+        Ok(())
+    }
+
+    // Blank lines and lines starting with # are skipped
+    pub fn apply_file_contents(&mut self, contents: &str) -> Result<(), ConfigError> {
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => self.set(key.trim(), value.trim())?,
+                _ => return Err(ConfigError::MalformedLine(i + 1, line.to_string())),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let num_players = self.num_players as usize;
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&num_players) {
+            return Err(ConfigError::PlayerCount(
+                MIN_PLAYERS,
+                MAX_PLAYERS,
+                self.num_players,
+            ));
+        }
+        if self.humans > self.num_players {
+            return Err(ConfigError::TooManyHumans(self.humans, self.num_players));
+        }
+        if self.starting_stack == 0 {
+            return Err(ConfigError::EmptyStack);
+        }
+        if self.sb == 0 || self.sb > self.bb {
+            return Err(ConfigError::InvalidBlinds(self.sb, self.bb));
+        }
+        Ok(())
+    }
+}
+
+// Accepts `--key value` and `--key=value`. A --config file is applied first wherever it appears,
+// so flags always win over the file.
+pub fn parse_args<S: AsRef<str>>(args: &[S]) -> Result<GameConfig, ConfigError> {
+    let mut flags = vec![];
+    let mut config_path = None;
+    let mut args = args.iter().map(|a| a.as_ref());
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            return Err(ConfigError::UnknownOption(arg.to_string()));
+        }
+        let arg = &arg[2..];
+        let (key, value) = match arg.find('=') {
+            Some(i) => (&arg[..i], &arg[i + 1..]),
+            None => match args.next() {
+                Some(value) => (arg, value),
+                None => return Err(ConfigError::MissingValue(arg.to_string())),
+            },
+        };
+        if key == "config" {
+            config_path = Some(value);
+        } else {
+            flags.push((key, value));
+        }
+    }
+
+    let mut config = GameConfig::default();
+    if let Some(path) = config_path {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| ConfigError::UnreadableFile(path.to_string(), e.to_string()))?;
+        config.apply_file_contents(&contents)?;
+    }
+    for (key, value) in flags {
+        config.set(key, value)?;
+    }
+    config.validate()?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_defaults_to_the_old_table() {
+        let config = parse_args::<&str>(&[]).unwrap();
+
+        assert_eq!(config, GameConfig::default());
+        assert_eq!(config.num_players, 6);
+        assert_eq!(config.humans, 1);
+    }
+
+    #[test]
+    fn it_parses_flags() {
+        let config = parse_args(&[
+            "--players",
+            "3",
+            "--humans=0",
+            "--stack",
+            "500",
+            "--sb",
+            "5",
+            "--bb",
+            "10",
+            "--ante",
+            "1",
+            "--ante-mode",
+            "bba",
+            "--seed",
+            "42",
+            "--variant",
+            "holdem",
+            "--structure",
+            "nl",
+            "--history",
+            "hands.txt",
+            "--save",
+            "session.json",
+        ])
+        .unwrap();
+
+        assert_eq!(config.num_players, 3);
+        assert_eq!(config.humans, 0);
+        assert_eq!(config.starting_stack, 500);
+        assert_eq!((config.sb, config.bb, config.ante), (5, 10, Some(1)));
+        assert_eq!(config.ante_mode, AnteMode::BigBlind);
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(config.structure, BettingStructure::NoLimit);
+        assert_eq!(config.history, Some("hands.txt".to_string()));
+        assert_eq!(config.save, Some("session.json".to_string()));
+        assert_eq!(config.resume, None);
+    }
+
+    #[test]
+    fn it_reads_config_file_contents() {
+        let mut config = GameConfig::default();
+        config
+            .apply_file_contents("# home game\nplayers = 9\n\nbb = 4\nsb=2\nstructure = limit\n")
+            .unwrap();
+
+        assert_eq!(config.num_players, 9);
+        assert_eq!((config.sb, config.bb), (2, 4));
+        assert_eq!(
+            config.structure,
+            BettingStructure::FixedLimit {
+                cap: DEFAULT_RAISE_CAP
+            }
+        );
+        assert_eq!(
+            config.apply_file_contents("players 9"),
+            Err(ConfigError::MalformedLine(1, "players 9".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_reports_bad_arguments() {
+        assert_eq!(
+            parse_args(&["--tables", "2"]),
+            Err(ConfigError::UnknownOption("tables".to_string()))
+        );
+        assert_eq!(
+            parse_args(&["--players"]),
+            Err(ConfigError::MissingValue("players".to_string()))
+        );
+        assert_eq!(
+            parse_args(&["--stack", "lots"]),
+            Err(ConfigError::InvalidValue(
+                "stack".to_string(),
+                "lots".to_string()
+            ))
+        );
+        assert!(parse_args(&["--variant", "stud"]).is_err());
+        assert!(parse_args(&["--structure", "spread-limit"]).is_err());
+        assert!(parse_args(&["--config", "/nonexistent/pokers.conf"]).is_err());
+    }
+
+    #[test]
+    fn it_validates_the_table() {
+        assert_eq!(
+            parse_args(&["--players", "11"]),
+            Err(ConfigError::PlayerCount(2, 10, 11))
+        );
+        assert_eq!(
+            parse_args(&["--players", "1"]),
+            Err(ConfigError::PlayerCount(2, 10, 1))
+        );
+        assert_eq!(
+            parse_args(&["--players", "2", "--humans", "3"]),
+            Err(ConfigError::TooManyHumans(3, 2))
+        );
+        assert_eq!(parse_args(&["--stack", "0"]), Err(ConfigError::EmptyStack));
+        assert_eq!(
+            parse_args(&["--sb", "5", "--bb", "2"]),
+            Err(ConfigError::InvalidBlinds(5, 2))
+        );
+    }
+}
@@ -1,9 +1,14 @@
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, SeedableRng, XorShiftRng};
 use std::fmt;
 
 use card::{ranks, suits, Card, CardVec};
+use card_set::CardSet;
+
+// Anything that needs to be reproducible from a game seed draws from this
+pub type GameRng = XorShiftRng;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Deck {
     pub cards: CardVec,
 }
@@ -19,7 +24,20 @@ impl Deck {
     }
 
     pub fn shuffle(&mut self) {
-        thread_rng().shuffle(&mut self.cards);
+        self.shuffle_with(&mut thread_rng());
+    }
+
+    pub fn shuffle_with<R: Rng>(&mut self, rng: &mut R) {
+        rng.shuffle(&mut self.cards);
+    }
+
+    // Pull known cards (hole cards, board, dead cards) out of the deck
+    pub fn remove_cards(&mut self, cards: CardSet) {
+        self.cards.retain(|c| !cards.contains(*c));
+    }
+
+    pub fn card_set(&self) -> CardSet {
+        self.cards.iter().cloned().collect()
     }
 }
 
@@ -28,7 +46,7 @@ impl fmt::Display for Deck {
         let mut display_string = "".to_string();
         for card in &self.cards {
             display_string.push_str(&card.to_string());
-            display_string.push_str("\n");
+            display_string.push('\n');
         }
 
         write!(f, "{}", display_string)
@@ -50,12 +68,51 @@ pub fn init_deck() -> Deck {
     Deck { cards }
 }
 
+pub fn init_deck_without(dead: CardSet) -> Deck {
+    let mut d = init_deck();
+    d.remove_cards(dead);
+    d
+}
+
 pub fn init_shuffled_deck() -> Deck {
     let mut d = init_deck();
     d.shuffle();
     d
 }
 
+pub fn init_shuffled_deck_with<R: Rng>(rng: &mut R) -> Deck {
+    let mut d = init_deck();
+    d.shuffle_with(rng);
+    d
+}
+
+// Deals `cards` in order, then the rest of an unshuffled deck
+pub fn init_stacked_deck(cards: &[Card]) -> Deck {
+    let mut d = init_deck_without(cards.iter().cloned().collect());
+    d.cards.extend(cards.iter().rev());
+    d
+}
+
+pub fn seeded_rng(seed: u64) -> GameRng {
+    // XorShift can't be seeded with all zeroes, so spread the seed out with splitmix64 first
+    let mut state = seed;
+    let mut next_word = || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        (z ^ (z >> 31)) as u32
+    };
+    let seed = [next_word(), next_word(), next_word(), next_word() | 1];
+    XorShiftRng::from_seed(seed)
+}
+
+// Each hand gets its own stream so any hand of a session can be reproduced from the game seed and
+// the hand number alone
+pub fn hand_rng(seed: u64, hand_count: u32) -> GameRng {
+    seeded_rng(seed ^ u64::from(hand_count).wrapping_mul(0xD1B5_4A32_D192_ED03))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,8 +125,8 @@ mod tests {
         let last_card = card_from_str("A", "C");
 
         assert_eq!(d.cards.len(), 52);
-        assert_eq!(&first_card, d.cards.iter().next().unwrap());
-        assert_eq!(&last_card, d.cards.iter().last().unwrap());
+        assert_eq!(&first_card, d.cards.first().unwrap());
+        assert_eq!(&last_card, d.cards.last().unwrap());
     }
 
     #[test]
@@ -88,4 +145,49 @@ mod tests {
         assert_eq!(h.len(), 5);
     }
 
+    #[test]
+    fn it_shuffles_reproducibly_from_a_seed() {
+        let d1 = init_shuffled_deck_with(&mut seeded_rng(42));
+        let d2 = init_shuffled_deck_with(&mut seeded_rng(42));
+        let d3 = init_shuffled_deck_with(&mut seeded_rng(43));
+
+        assert_eq!(d1.cards, d2.cards);
+        assert_ne!(d1.cards, d3.cards);
+        assert_ne!(d1.cards, init_deck().cards);
+    }
+
+    #[test]
+    fn it_gives_each_hand_its_own_stream() {
+        let d1 = init_shuffled_deck_with(&mut hand_rng(7, 1));
+        let d2 = init_shuffled_deck_with(&mut hand_rng(7, 2));
+
+        assert_ne!(d1.cards, d2.cards);
+        assert_eq!(d1.cards, init_shuffled_deck_with(&mut hand_rng(7, 1)).cards);
+    }
+
+    #[test]
+    fn it_creates_deck_without_dead_cards() {
+        let dead = CardSet::from(&vec![card_from_str("A", "S"), card_from_str("2", "C")]);
+        let d = init_deck_without(dead);
+
+        assert_eq!(d.cards.len(), 50);
+        assert!(d.card_set().is_disjoint(dead));
+        assert_eq!(d.card_set() | dead, CardSet::full());
+    }
+
+    #[test]
+    fn it_deals_a_stacked_deck_in_order() {
+        let top = vec![
+            card_from_str("A", "S"),
+            card_from_str("2", "C"),
+            card_from_str("K", "H"),
+        ];
+        let mut d = init_stacked_deck(&top);
+
+        assert_eq!(d.cards.len(), 52);
+        assert_eq!(d.deal_cards(2), top[..2].to_vec());
+        assert_eq!(d.deal_cards(1), top[2..].to_vec());
+        assert_eq!(d.card_set(), CardSet::full() - CardSet::from(&top));
+    }
+
 }
@@ -0,0 +1,403 @@
+use rand::Rng;
+
+use card::{Card, CardVec};
+use card_set::CardSet;
+use deck::{init_deck_without, seeded_rng};
+use evaluator::{evaluate_set, HandValue};
+
+// All-in equity calculation. Hands are compared with the lookup table evaluator, which orders (and
+// ties) hands exactly as `hand::find_best_hand` does.
+
+pub const BOARD_SIZE: usize = 5;
+pub const DECK_SIZE: usize = 52;
+const HOLE_CARDS: usize = 2;
+// z score for a two sided 95% confidence interval
+pub const Z_95: f64 = 1.96;
+// Monte Carlo runs check for convergence in batches of this many trials
+const BATCH_SIZE: u64 = 1000;
+
+#[derive(Debug, Fail)]
+pub enum EquityError {
+    #[fail(display = "Equity needs at least two hands, got {}", _0)]
+    NotEnoughHands(usize),
+    #[fail(display = "Each hand needs exactly two hole cards, got {:?}", _0)]
+    InvalidHoleCards(CardVec),
+    #[fail(display = "The board can have at most five cards, got {}", _0)]
+    TooManyBoardCards(usize),
+    #[fail(display = "{:?} is dealt more than once", _0)]
+    DuplicateCard(Card),
+    #[fail(
+        display = "Dealing the hands and board takes {} cards, more than the deck holds",
+        _0
+    )]
+    NotEnoughCards(usize),
+    #[fail(display = "No combination of the given ranges can be dealt together")]
+    NoValidMatchups,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerEquity {
+    // Runouts this hand won outright, shared, or lost
+    pub wins: u64,
+    pub ties: u64,
+    pub losses: u64,
+    // Pot share summed over runouts; a two way tie counts as half a win
+    pub shares: f64,
+    // Sum of squared pot shares, for the sample variance of a Monte Carlo estimate
+    pub share_squares: f64,
+}
+
+impl PlayerEquity {
+    pub fn equity(&self) -> f64 {
+        match self.runouts() {
+            0 => 0.0,
+            runouts => self.shares / runouts as f64,
+        }
+    }
+
+    pub fn runouts(&self) -> u64 {
+        self.wins + self.ties + self.losses
+    }
+
+    // Standard error of the equity when the runouts are a random sample
+    pub fn std_error(&self) -> f64 {
+        let n = self.runouts() as f64;
+        if n < 2.0 {
+            return f64::INFINITY;
+        }
+        let mean = self.shares / n;
+        let variance = (self.share_squares / n - mean * mean).max(0.0) * n / (n - 1.0);
+        (variance / n).sqrt()
+    }
+
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        let margin = z * self.std_error();
+        let equity = self.equity();
+        ((equity - margin).max(0.0), (equity + margin).min(1.0))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Equity {
+    pub players: Vec<PlayerEquity>,
+    pub runouts: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MonteCarloConfig {
+    pub max_trials: u64,
+    // Stop early once every hand's standard error is at or below this
+    pub target_std_error: Option<f64>,
+    pub seed: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    pub players: Vec<PlayerEquity>,
+    pub trials: u64,
+    // Whether the target standard error was reached before running out of trials
+    pub converged: bool,
+}
+
+// Known cards for an equity calculation, validated and gathered up front
+pub struct Spot {
+    pub hands: Vec<CardSet>,
+    pub board: CardSet,
+    pub board_cards: usize,
+    pub known: CardSet,
+}
+
+pub fn init_spot(hands: &[CardVec], board: &[Card], dead: &[Card]) -> Result<Spot, EquityError> {
+    if hands.len() < 2 {
+        return Err(EquityError::NotEnoughHands(hands.len()));
+    }
+    if board.len() > BOARD_SIZE {
+        return Err(EquityError::TooManyBoardCards(board.len()));
+    }
+    if let Some(hand) = hands.iter().find(|h| h.len() != HOLE_CARDS) {
+        return Err(EquityError::InvalidHoleCards(hand.clone()));
+    }
+
+    let mut known = CardSet::empty();
+    for card in hands.iter().flat_map(|h| h.iter()).chain(board).chain(dead) {
+        if !known.insert(*card) {
+            return Err(EquityError::DuplicateCard(*card));
+        }
+    }
+    let needed = known.count() as usize + BOARD_SIZE - board.len();
+    if needed > DECK_SIZE {
+        return Err(EquityError::NotEnoughCards(needed));
+    }
+
+    Ok(Spot {
+        hands: hands.iter().map(CardSet::from).collect(),
+        board: CardSet::from(board),
+        board_cards: board.len(),
+        known,
+    })
+}
+
+// Exhaustively deals every remaining board and tallies each hand's results
+pub fn exact_equity(
+    hands: &[CardVec],
+    board: &[Card],
+    dead: &[Card],
+) -> Result<Equity, EquityError> {
+    let spot = init_spot(hands, board, dead)?;
+    let remaining = init_deck_without(spot.known).cards;
+    let mut players = vec![PlayerEquity::default(); hands.len()];
+    let mut runouts = 0;
+
+    for_each_runout(
+        &remaining,
+        BOARD_SIZE - spot.board_cards,
+        spot.board,
+        &mut |board| {
+            tally_runout(&spot.hands, board, &mut players);
+            runouts += 1;
+        },
+    );
+
+    Ok(Equity { players, runouts })
+}
+
+// Samples random boards instead of enumerating them all, for spots (e.g. multiway preflop) where
+// exact enumeration is too slow
+pub fn monte_carlo_equity(
+    hands: &[CardVec],
+    board: &[Card],
+    dead: &[Card],
+    config: &MonteCarloConfig,
+) -> Result<Estimate, EquityError> {
+    let spot = init_spot(hands, board, dead)?;
+    let mut remaining = init_deck_without(spot.known).cards;
+    let to_deal = BOARD_SIZE - spot.board_cards;
+    let mut rng = seeded_rng(config.seed);
+    let mut players = vec![PlayerEquity::default(); hands.len()];
+    let mut trials = 0;
+
+    while trials < config.max_trials {
+        let batch = BATCH_SIZE.min(config.max_trials - trials);
+        for _ in 0..batch {
+            let board = sample_runout(&mut remaining, to_deal, spot.board, &mut rng);
+            tally_runout(&spot.hands, board, &mut players);
+        }
+        trials += batch;
+
+        if converged(&players, config.target_std_error) {
+            return Ok(Estimate {
+                players,
+                trials,
+                converged: true,
+            });
+        }
+    }
+
+    Ok(Estimate {
+        players,
+        trials,
+        converged: false,
+    })
+}
+
+fn converged(players: &[PlayerEquity], target: Option<f64>) -> bool {
+    match target {
+        Some(target) => players.iter().all(|p| p.std_error() <= target),
+        None => false,
+    }
+}
+
+// Partial Fisher-Yates shuffle: the first `count` cards become the sampled runout
+pub fn sample_runout<R: Rng>(
+    cards: &mut [Card],
+    count: usize,
+    board: CardSet,
+    rng: &mut R,
+) -> CardSet {
+    let mut board = board;
+    for i in 0..count {
+        let j = rng.gen_range(i, cards.len());
+        cards.swap(i, j);
+        board.insert(cards[i]);
+    }
+    board
+}
+
+pub fn for_each_runout<F: FnMut(CardSet)>(cards: &[Card], count: usize, board: CardSet, f: &mut F) {
+    if count == 0 {
+        f(board);
+        return;
+    }
+    for i in 0..cards.len() {
+        let mut board = board;
+        board.insert(cards[i]);
+        for_each_runout(&cards[i + 1..], count - 1, board, f);
+    }
+}
+
+// Scores one complete board, crediting the best hand(s)
+pub fn tally_runout(hands: &[CardSet], board: CardSet, players: &mut [PlayerEquity]) {
+    let values: Vec<HandValue> = hands.iter().map(|h| evaluate_set(*h | board)).collect();
+    let best = *values.iter().max().unwrap();
+    let winners = values.iter().filter(|v| **v == best).count();
+
+    for (player, value) in players.iter_mut().zip(values) {
+        if value < best {
+            player.losses += 1;
+        } else if winners == 1 {
+            player.wins += 1;
+            player.shares += 1.0;
+            player.share_squares += 1.0;
+        } else {
+            let share = 1.0 / winners as f64;
+            player.ties += 1;
+            player.shares += share;
+            player.share_squares += share * share;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::parse_cards;
+
+    fn hands(hands: &[&str]) -> Vec<CardVec> {
+        hands.iter().map(|h| parse_cards(h).unwrap()).collect()
+    }
+
+    #[test]
+    fn it_counts_river_outs_exactly() {
+        let hands = hands(&["AsAh", "KsKh"]);
+        let board = parse_cards("2c7d9cJd").unwrap();
+        let equity = exact_equity(&hands, &board, &[]).unwrap();
+
+        assert_eq!(equity.runouts, 44);
+        assert_eq!(equity.players[0].wins, 42);
+        assert_eq!(equity.players[1].wins, 2);
+        assert_eq!(equity.players[0].ties, 0);
+        assert_eq!(equity.players[1].losses, 42);
+    }
+
+    #[test]
+    fn it_removes_dead_cards_from_the_runouts() {
+        let hands = hands(&["AsAh", "KsKh"]);
+        let board = parse_cards("2c7d9cJd").unwrap();
+        let dead = parse_cards("Kc").unwrap();
+        let equity = exact_equity(&hands, &board, &dead).unwrap();
+
+        assert_eq!(equity.runouts, 43);
+        assert_eq!(equity.players[1].wins, 1);
+    }
+
+    #[test]
+    fn it_splits_ties() {
+        let hands = hands(&["2s3s", "2h3h", "4d4c"]);
+        let board = parse_cards("AsKdQhJcTs").unwrap();
+        let equity = exact_equity(&hands, &board, &[]).unwrap();
+
+        assert_eq!(equity.runouts, 1);
+        for player in &equity.players {
+            assert_eq!(player.ties, 1);
+            assert!((player.equity() - 1.0 / 3.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn it_enumerates_flop_runouts() {
+        let hands = hands(&["AhKh", "QsQd"]);
+        let board = parse_cards("7h8h2c").unwrap();
+        let equity = exact_equity(&hands, &board, &[]).unwrap();
+
+        assert_eq!(equity.runouts, 990);
+        let total: f64 = equity.players.iter().map(|p| p.equity()).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        for player in &equity.players {
+            assert_eq!(player.runouts(), 990);
+        }
+        // Flush and overcard draw is a slight favourite to the overpair
+        assert!(equity.players[0].equity() > 0.5);
+    }
+
+    #[test]
+    fn it_estimates_equity_close_to_the_exact_answer() {
+        let hands = hands(&["AhKh", "QsQd"]);
+        let board = parse_cards("7h8h2c").unwrap();
+        let exact = exact_equity(&hands, &board, &[]).unwrap();
+        let config = MonteCarloConfig {
+            max_trials: 20000,
+            target_std_error: None,
+            seed: 1,
+        };
+        let estimate = monte_carlo_equity(&hands, &board, &[], &config).unwrap();
+
+        assert_eq!(estimate.trials, 20000);
+        assert!(!estimate.converged);
+        for (estimated, exact) in estimate.players.iter().zip(exact.players.iter()) {
+            let (low, high) = estimated.confidence_interval(4.0);
+            assert!(low <= exact.equity() && exact.equity() <= high);
+            assert!(estimated.std_error() < 0.01);
+        }
+    }
+
+    #[test]
+    fn it_stops_once_the_target_error_is_reached() {
+        let hands = hands(&["AsAd", "KsKd", "7c8c"]);
+        let config = MonteCarloConfig {
+            max_trials: 1_000_000,
+            target_std_error: Some(0.01),
+            seed: 2,
+        };
+        let estimate = monte_carlo_equity(&hands, &[], &[], &config).unwrap();
+
+        assert!(estimate.converged);
+        assert!(estimate.trials < config.max_trials);
+        assert!(estimate.players.iter().all(|p| p.std_error() <= 0.01));
+        let total: f64 = estimate.players.iter().map(|p| p.equity()).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_reproduces_estimates_from_a_seed() {
+        let hands = hands(&["AsAd", "KsKd"]);
+        let config = MonteCarloConfig {
+            max_trials: 2000,
+            target_std_error: None,
+            seed: 3,
+        };
+
+        assert_eq!(
+            monte_carlo_equity(&hands, &[], &[], &config).unwrap(),
+            monte_carlo_equity(&hands, &[], &[], &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_rejects_bad_spots() {
+        let board = parse_cards("2c7d9c").unwrap();
+        assert!(exact_equity(&hands(&["AsAh"]), &board, &[]).is_err());
+        assert!(exact_equity(&hands(&["AsAh", "Ks"]), &board, &[]).is_err());
+        assert!(exact_equity(&hands(&["AsAh", "Ks2c"]), &board, &[]).is_err());
+        assert!(exact_equity(
+            &hands(&["AsAh", "KsKh"]),
+            &parse_cards("2c3c4c5c6c7c").unwrap(),
+            &[]
+        )
+        .is_err());
+
+        // Two hands, a flop and 44 dead cards leave one card for the turn and river
+        let hs = hands(&["AsAh", "KsKh"]);
+        let known: CardSet = hs
+            .iter()
+            .flat_map(|h| h.iter())
+            .chain(&board)
+            .cloned()
+            .collect();
+        let dead = init_deck_without(known).cards;
+        assert!(matches!(
+            exact_equity(&hs, &board, &dead[..44]),
+            Err(EquityError::NotEnoughCards(53))
+        ));
+        assert!(exact_equity(&hs, &board, &dead[..43]).is_ok());
+    }
+}
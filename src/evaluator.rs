@@ -0,0 +1,445 @@
+use std::sync::OnceLock;
+
+use card::{ranks, suits, Card, Rank, RankVec};
+use card_set::CardSet;
+use hand::Hand;
+use hand_rankings::HandRank;
+
+// Table driven evaluator for 5, 6 or 7 card holdings.
+//
+// A holding is split into its rank counts and, when five or more cards share a suit, that suit's
+// rank mask. Rank counts are perfect hashed (lexicographic index among all base-5 count vectors
+// with the same card total) into a table of best non-flush values, and flush masks index straight
+// into a 13 bit table, so evaluation is a handful of lookups with no allocation or sorting.
+//
+// `hand_rankings::rank_hand` stays around as the reference implementation these tables are
+// checked against.
+
+const NUM_RANKS: usize = 13;
+const MAX_CARDS: usize = 7;
+const RANK_SHIFT: u32 = 20;
+const KICKER_BITS: u32 = 4;
+
+const HAND_RANKS: [HandRank; 9] = [
+    HandRank::HighCard,
+    HandRank::Pair,
+    HandRank::TwoPair,
+    HandRank::ThreeOfAKind,
+    HandRank::Straight,
+    HandRank::Flush,
+    HandRank::FullHouse,
+    HandRank::FourOfAKind,
+    HandRank::StraightFlush,
+];
+
+// Hand strength packed into a u32: the HandRank in the high bits, followed by up to five kicker
+// ranks a nibble each, most significant first. Kickers are laid out exactly as
+// `hand_rankings::get_kickers` orders them, so comparing two values orders hands the same way
+// comparing `Hand`s does.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct HandValue(u32);
+
+impl HandValue {
+    fn new(hand_rank: HandRank, kickers: &[Rank]) -> HandValue {
+        let mut val = (hand_rank as u32) << RANK_SHIFT;
+        for (i, kicker) in kickers.iter().enumerate() {
+            val |= kicker.val() << (RANK_SHIFT - KICKER_BITS * (i as u32 + 1));
+        }
+        HandValue(val)
+    }
+
+    pub fn val(self) -> u32 {
+        self.0
+    }
+
+    pub fn hand_rank(self) -> HandRank {
+        HAND_RANKS[(self.0 >> RANK_SHIFT) as usize]
+    }
+
+    pub fn kickers(self) -> RankVec {
+        (0..num_kickers(self.hand_rank()))
+            .map(|i| {
+                let shift = RANK_SHIFT - KICKER_BITS * (i as u32 + 1);
+                ranks()[((self.0 >> shift) & 0xf) as usize]
+            })
+            .collect()
+    }
+}
+
+fn num_kickers(hand_rank: HandRank) -> usize {
+    match hand_rank {
+        HandRank::HighCard | HandRank::Flush => 5,
+        HandRank::Pair => 4,
+        HandRank::TwoPair | HandRank::ThreeOfAKind => 3,
+        HandRank::FullHouse | HandRank::FourOfAKind => 2,
+        HandRank::Straight | HandRank::StraightFlush => 1,
+    }
+}
+
+// Packs an already ranked hand, for cross checking against the reference implementation
+pub fn hand_value(hand: &Hand) -> HandValue {
+    HandValue::new(hand.hand_rank, &hand.kickers)
+}
+
+pub fn evaluate(cards: &[Card]) -> HandValue {
+    let mut counts = [0u8; NUM_RANKS];
+    let mut suit_masks = [0u16; 4];
+    for card in cards {
+        counts[card.rank.val() as usize] += 1;
+        suit_masks[card.suit.val() as usize] |= 1 << card.rank.val();
+    }
+
+    evaluate_parts(&counts, &suit_masks, cards.len())
+}
+
+pub fn evaluate_set(cards: CardSet) -> HandValue {
+    let mut counts = [0u8; NUM_RANKS];
+    let mut suit_masks = [0u16; 4];
+    for suit in &suits() {
+        let mask = cards.suit_mask(*suit);
+        suit_masks[suit.val() as usize] = mask;
+        for (r, count) in counts.iter_mut().enumerate() {
+            *count += ((mask >> r) & 1) as u8;
+        }
+    }
+
+    evaluate_parts(&counts, &suit_masks, cards.count() as usize)
+}
+
+// Shared by anything that can cheaply produce rank counts and per suit rank masks
+pub fn evaluate_parts(
+    counts: &[u8; NUM_RANKS],
+    suit_masks: &[u16; 4],
+    num_cards: usize,
+) -> HandValue {
+    assert!(
+        (5..=MAX_CARDS).contains(&num_cards),
+        "Can only evaluate 5 to 7 cards, got {}",
+        num_cards
+    );
+    let tables = tables();
+
+    let mut best = tables.ranks[num_cards][tables.index(counts, num_cards)];
+    for mask in suit_masks {
+        if mask.count_ones() >= 5 {
+            best = best.max(tables.flushes[*mask as usize]);
+        }
+    }
+
+    best
+}
+
+struct Tables {
+    // offsets[digit][positions_left][cards_left]: how many count vectors sort before one with
+    // `digit` at this position, given what remains to be placed
+    offsets: [[[usize; MAX_CARDS + 1]; NUM_RANKS]; 5],
+    ranks: Vec<Vec<HandValue>>,
+    flushes: Vec<HandValue>,
+}
+
+impl Tables {
+    fn index(&self, counts: &[u8; NUM_RANKS], num_cards: usize) -> usize {
+        let mut index = 0;
+        let mut left = num_cards;
+        for (i, &count) in counts.iter().enumerate() {
+            index += self.offsets[count as usize][NUM_RANKS - 1 - i][left];
+            left -= count as usize;
+            if left == 0 {
+                break;
+            }
+        }
+        index
+    }
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(build_tables)
+}
+
+fn build_tables() -> Tables {
+    // sequences[len][sum]: number of rank count vectors of length len summing to sum
+    let mut sequences = [[0usize; MAX_CARDS + 1]; NUM_RANKS + 1];
+    sequences[0][0] = 1;
+    for len in 1..=NUM_RANKS {
+        for sum in 0..=MAX_CARDS {
+            sequences[len][sum] = (0..=sum.min(4)).map(|d| sequences[len - 1][sum - d]).sum();
+        }
+    }
+
+    let mut offsets = [[[0usize; MAX_CARDS + 1]; NUM_RANKS]; 5];
+    for digit in 1..5 {
+        for len in 0..NUM_RANKS {
+            for left in 0..=MAX_CARDS {
+                offsets[digit][len][left] = offsets[digit - 1][len][left]
+                    + if left >= digit - 1 {
+                        sequences[len][left - (digit - 1)]
+                    } else {
+                        0
+                    };
+            }
+        }
+    }
+
+    let mut tables = Tables {
+        offsets,
+        ranks: vec![vec![]; MAX_CARDS + 1],
+        flushes: vec![HandValue(0); 1 << NUM_RANKS],
+    };
+
+    for (num_cards, &size) in sequences[NUM_RANKS].iter().enumerate().skip(5) {
+        let mut table = vec![HandValue(0); size];
+        let mut counts = [0u8; NUM_RANKS];
+        fill_rank_table(&tables, &mut table, &mut counts, 0, num_cards);
+        tables.ranks[num_cards] = table;
+    }
+
+    for mask in 0..(1u16 << NUM_RANKS) {
+        if (5..=MAX_CARDS as u32).contains(&mask.count_ones()) {
+            tables.flushes[mask as usize] = flush_value(mask);
+        }
+    }
+
+    tables
+}
+
+fn fill_rank_table(
+    tables: &Tables,
+    table: &mut Vec<HandValue>,
+    counts: &mut [u8; NUM_RANKS],
+    position: usize,
+    left: usize,
+) {
+    if position == NUM_RANKS {
+        if left == 0 {
+            let num_cards = counts.iter().map(|&c| c as usize).sum();
+            table[tables.index(counts, num_cards)] = unsuited_value(counts);
+        }
+        return;
+    }
+
+    for count in 0..=left.min(4) {
+        counts[position] = count as u8;
+        fill_rank_table(tables, table, counts, position + 1, left - count);
+    }
+    counts[position] = 0;
+}
+
+// Highest straight in a rank mask, as the rank of its top card
+fn straight_top(mask: u16) -> Option<Rank> {
+    let ace = 1 << Rank::Ace.val();
+    let wheel = ace | 0b1111;
+    (4..NUM_RANKS)
+        .rev()
+        .find(|&top| {
+            let run = 0b11111 << (top - 4);
+            mask & run == run
+        })
+        .map(|top| ranks()[top])
+        .or(if mask & wheel == wheel {
+            Some(Rank::Five)
+        } else {
+            None
+        })
+}
+
+fn ranks_in(mask: u16) -> RankVec {
+    ranks()
+        .iter()
+        .rev()
+        .cloned()
+        .filter(|r| mask & (1 << r.val()) != 0)
+        .collect()
+}
+
+fn flush_value(mask: u16) -> HandValue {
+    match straight_top(mask) {
+        Some(top) => HandValue::new(HandRank::StraightFlush, &[top]),
+        None => HandValue::new(HandRank::Flush, &ranks_in(mask)[..5]),
+    }
+}
+
+// Best hand makeable from a set of ranks, ignoring suits
+fn unsuited_value(counts: &[u8; NUM_RANKS]) -> HandValue {
+    let with_count = |n: u8| -> RankVec {
+        ranks()
+            .iter()
+            .rev()
+            .cloned()
+            .filter(|r| counts[r.val() as usize] >= n)
+            .collect()
+    };
+    let kickers = |used: &[Rank], n: usize| -> RankVec {
+        with_count(1)
+            .into_iter()
+            .filter(|r| !used.contains(r))
+            .take(n)
+            .collect()
+    };
+    let mask = with_count(1).iter().fold(0u16, |m, r| m | 1 << r.val());
+    let quads = with_count(4);
+    let trips = with_count(3);
+    let pairs = with_count(2);
+
+    if let Some(&quad) = quads.first() {
+        let mut k = vec![quad];
+        k.extend(kickers(&[quad], 1));
+        return HandValue::new(HandRank::FourOfAKind, &k);
+    }
+    if let Some(&trip) = trips.first() {
+        if let Some(&pair) = pairs.iter().find(|&&p| p != trip) {
+            return HandValue::new(HandRank::FullHouse, &[trip, pair]);
+        }
+    }
+    if let Some(top) = straight_top(mask) {
+        return HandValue::new(HandRank::Straight, &[top]);
+    }
+    if let Some(&trip) = trips.first() {
+        let mut k = vec![trip];
+        k.extend(kickers(&[trip], 2));
+        return HandValue::new(HandRank::ThreeOfAKind, &k);
+    }
+    if pairs.len() >= 2 {
+        let mut k = vec![pairs[0], pairs[1]];
+        let kicker = kickers(&k, 1);
+        k.extend(kicker);
+        return HandValue::new(HandRank::TwoPair, &k);
+    }
+    if let Some(&pair) = pairs.first() {
+        let mut k = vec![pair];
+        k.extend(kickers(&[pair], 3));
+        return HandValue::new(HandRank::Pair, &k);
+    }
+
+    HandValue::new(HandRank::HighCard, &kickers(&[], 5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::card_from_str;
+    use deck::init_deck;
+    use hand::{find_best_hand, init_hand};
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    use test_utils::*;
+
+    #[test]
+    fn it_matches_the_reference_ranking_on_fixtures() {
+        let fixtures = vec![
+            high_card_hand(),
+            pair_hand(),
+            pair_hand_2(),
+            pair_hand_3(),
+            two_pair_hand(),
+            two_pair_hand_2(),
+            two_pair_hand_3(),
+            three_of_a_kind_hand(),
+            straight_hand(),
+            wheel_straight_hand(),
+            flush_hand(),
+            full_house_hand(),
+            four_of_a_kind_hand(),
+            straight_flush_hand(),
+            wheel_straight_flush_hand(),
+        ];
+
+        for cards in fixtures {
+            let reference = init_hand(cards.clone());
+            let value = evaluate(&cards);
+
+            assert_eq!(value, hand_value(&reference));
+            assert_eq!(value.hand_rank(), reference.hand_rank);
+            assert_eq!(value.kickers(), reference.kickers);
+        }
+    }
+
+    #[test]
+    fn it_matches_find_best_hand_on_random_holdings() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut deck = init_deck();
+
+        for num_cards in 5..=7 {
+            for _ in 0..500 {
+                rng.shuffle(&mut deck.cards);
+                let cards = deck.cards[..num_cards].to_vec();
+                let reference = find_best_hand(cards.clone()).unwrap();
+
+                assert_eq!(evaluate(&cards), hand_value(&reference), "{:?}", cards);
+            }
+        }
+    }
+
+    #[test]
+    fn it_evaluates_card_sets_like_card_slices() {
+        let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+        let mut deck = init_deck();
+
+        for _ in 0..300 {
+            rng.shuffle(&mut deck.cards);
+            let cards = deck.cards[..7].to_vec();
+
+            assert_eq!(evaluate_set(CardSet::from(&cards)), evaluate(&cards));
+        }
+    }
+
+    #[test]
+    fn it_orders_hands_like_the_reference() {
+        let mut rng = XorShiftRng::from_seed([4, 3, 2, 1]);
+        let mut deck = init_deck();
+
+        for _ in 0..300 {
+            rng.shuffle(&mut deck.cards);
+            let a = deck.cards[..7].to_vec();
+            let b = deck.cards[7..14].to_vec();
+            let (best_a, best_b) = (find_best_hand(a.clone()), find_best_hand(b.clone()));
+
+            assert_eq!(
+                evaluate(&a).cmp(&evaluate(&b)),
+                best_a.partial_cmp(&best_b).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn it_prefers_a_flush_over_a_board_straight() {
+        let cards = vec![
+            card_from_str("6", "H"),
+            card_from_str("7", "S"),
+            card_from_str("8", "H"),
+            card_from_str("9", "D"),
+            card_from_str("T", "H"),
+            card_from_str("2", "H"),
+            card_from_str("K", "H"),
+        ];
+
+        let value = evaluate(&cards);
+        assert_eq!(value.hand_rank(), HandRank::Flush);
+        assert_eq!(
+            value.kickers(),
+            vec![Rank::King, Rank::Ten, Rank::Eight, Rank::Six, Rank::Two]
+        );
+    }
+
+    #[test]
+    fn it_finds_the_best_full_house_from_two_sets() {
+        let cards = vec![
+            card_from_str("4", "H"),
+            card_from_str("4", "S"),
+            card_from_str("4", "D"),
+            card_from_str("9", "D"),
+            card_from_str("9", "H"),
+            card_from_str("9", "C"),
+            card_from_str("K", "H"),
+        ];
+
+        let value = evaluate(&cards);
+        assert_eq!(value.hand_rank(), HandRank::FullHouse);
+        assert_eq!(value.kickers(), vec![Rank::Nine, Rank::Four]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_rejects_too_few_cards() {
+        evaluate(&high_card_hand()[..4]);
+    }
+}
@@ -0,0 +1,247 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+
+use card::{cards_to_short_string, CardVec};
+use gamestate::{BettingStructure, Blinds, Street};
+use hand_rankings::HandRank;
+use player::{PlayerAction, PlayerId};
+
+// Everything that happens at the table, in order. Observers registered on a GameState get each
+// one as it happens.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    GameStarted {
+        seed: u64,
+    },
+    // Seats are in table order as (player, seat index, name, stack), with stacks from before antes
+    // and blinds
+    HandStarted {
+        hand_number: u32,
+        table_size: usize,
+        button: PlayerId,
+        small_blind: PlayerId,
+        big_blind: PlayerId,
+        blinds: Blinds,
+        structure: BettingStructure,
+        seats: Vec<(PlayerId, usize, String, u32)>,
+    },
+    AntePosted {
+        player: PlayerId,
+        amount: u32,
+    },
+    SmallBlindPosted {
+        player: PlayerId,
+        amount: u32,
+    },
+    BigBlindPosted {
+        player: PlayerId,
+        amount: u32,
+    },
+    HoleCardsDealt {
+        player: PlayerId,
+        cards: CardVec,
+    },
+    ActionTaken {
+        player: PlayerId,
+        action: PlayerAction,
+        all_in: bool,
+    },
+    ActionRejected {
+        player: PlayerId,
+        action: PlayerAction,
+        reason: String,
+    },
+    // cards are the ones just dealt, board is all of them
+    StreetDealt {
+        street: Street,
+        cards: CardVec,
+        board: CardVec,
+    },
+    HandShown {
+        player: PlayerId,
+        cards: CardVec,
+        rank: HandRank,
+    },
+    // Pot 0 is the main pot, side pots follow in order
+    PotAwarded {
+        pot: usize,
+        player: PlayerId,
+        amount: u32,
+    },
+    HandFinished {
+        hand_number: u32,
+        stacks: Vec<(PlayerId, u32)>,
+    },
+    PlayerEliminated {
+        player: PlayerId,
+    },
+    // No winner when the game was stopped with players still left
+    GameOver {
+        winner: Option<PlayerId>,
+    },
+}
+
+pub trait GameObserver: fmt::Debug {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+// Lets another thread, or a test, collect events off a channel. A hung up receiver just stops
+// getting them.
+impl GameObserver for Sender<GameEvent> {
+    fn on_event(&mut self, event: &GameEvent) {
+        let _ = self.send(event.clone());
+    }
+}
+
+// Shares an observer with the code running the game, so it can be checked on between hands
+impl<T: GameObserver> GameObserver for Rc<RefCell<T>> {
+    fn on_event(&mut self, event: &GameEvent) {
+        self.borrow_mut().on_event(event);
+    }
+}
+
+// Writes each event to stdout as a line of text
+#[derive(Debug)]
+pub struct PrintObserver {
+    // Off when someone is playing at the terminal
+    pub show_hole_cards: bool,
+}
+
+impl GameObserver for PrintObserver {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::HoleCardsDealt { .. } if !self.show_hole_cards => (),
+            _ => println!("{}", event),
+        }
+    }
+}
+
+pub fn init_print_observer(show_hole_cards: bool) -> PrintObserver {
+    PrintObserver { show_hole_cards }
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GameEvent::GameStarted { seed } => write!(f, "Game starting with seed {}", seed),
+            GameEvent::HandStarted {
+                hand_number,
+                button,
+                ..
+            } => write!(
+                f,
+                "Hand {} starting, button on player {}",
+                hand_number, button
+            ),
+            GameEvent::AntePosted { player, amount } => {
+                write!(f, "Player {} posts an ante of {} chips", player, amount)
+            }
+            GameEvent::SmallBlindPosted { player, amount } => {
+                write!(f, "Player {} posts small blind of {} chips", player, amount)
+            }
+            GameEvent::BigBlindPosted { player, amount } => {
+                write!(f, "Player {} posts big blind of {} chips", player, amount)
+            }
+            GameEvent::HoleCardsDealt { player, ref cards } => {
+                write!(
+                    f,
+                    "Dealt [{}] to player {}",
+                    cards_to_short_string(cards),
+                    player
+                )
+            }
+            GameEvent::ActionTaken {
+                player,
+                action,
+                all_in,
+            } => {
+                match action {
+                    PlayerAction::Bet(n) => write!(f, "Player {} bets {} chips", player, n)?,
+                    PlayerAction::Raise(n) => write!(f, "Player {} raises to {} chips", player, n)?,
+                    PlayerAction::Call(n) => write!(f, "Player {} calls {} chips", player, n)?,
+                    PlayerAction::Check => write!(f, "Player {} checks", player)?,
+                    PlayerAction::Fold => write!(f, "Player {} folds", player)?,
+                }
+                if all_in {
+                    write!(f, " and is all in")?;
+                }
+                Ok(())
+            }
+            GameEvent::ActionRejected {
+                player,
+                action,
+                ref reason,
+            } => write!(f, "Player {} tried {:?}: {}", player, action, reason),
+            GameEvent::StreetDealt {
+                street, ref board, ..
+            } => match street {
+                Street::Showdown => write!(f, "Showdown"),
+                _ => write!(f, "{:?}: [{}]", street, cards_to_short_string(board)),
+            },
+            GameEvent::HandShown {
+                player,
+                ref cards,
+                rank,
+            } => write!(
+                f,
+                "Player {} shows [{}] for {:?}",
+                player,
+                cards_to_short_string(cards),
+                rank
+            ),
+            GameEvent::PotAwarded {
+                pot,
+                player,
+                amount,
+            } => {
+                let pot = if pot == 0 {
+                    "the main pot".to_string()
+                } else {
+                    format!("side pot {}", pot)
+                };
+                write!(f, "Player {} wins {} chips from {}", player, amount, pot)
+            }
+            GameEvent::HandFinished { hand_number, .. } => write!(f, "Hand {} over", hand_number),
+            GameEvent::PlayerEliminated { player } => write!(f, "Player {} is out", player),
+            GameEvent::GameOver { winner: Some(id) } => write!(f, "Game over! Player {} wins", id),
+            GameEvent::GameOver { winner: None } => write!(f, "Game over!"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::parse_cards;
+
+    #[test]
+    fn it_describes_events() {
+        let raise = GameEvent::ActionTaken {
+            player: 3,
+            action: PlayerAction::Raise(40),
+            all_in: true,
+        };
+        let flop = GameEvent::StreetDealt {
+            street: Street::Flop,
+            cards: parse_cards("7h8h2c").unwrap(),
+            board: parse_cards("7h8h2c").unwrap(),
+        };
+        let side_pot = GameEvent::PotAwarded {
+            pot: 1,
+            player: 0,
+            amount: 25,
+        };
+
+        assert_eq!(
+            raise.to_string(),
+            "Player 3 raises to 40 chips and is all in"
+        );
+        assert_eq!(flop.to_string(), "Flop: [7h 8h 2c]");
+        assert_eq!(
+            side_pot.to_string(),
+            "Player 0 wins 25 chips from side pot 1"
+        );
+    }
+}
@@ -1,17 +1,75 @@
 use player::*;
 use gamestate::*;
+use config::{GameConfig, GameVariant};
+use event::init_print_observer;
+use history::init_history_writer;
+use session::{init_session, load_session, Session};
+use std::cell::RefCell;
+use std::fs::OpenOptions;
+use std::io;
+use std::rc::Rc;
 
-pub fn play_game(
-    num_players: u32,
-    user_player: bool,
-    starting_stack: u32,
-    sb: u32,
-    bb: u32,
-    ante: Option<u32>,
-) {
-    let players = init_players(num_players, user_player, starting_stack);
-    let blinds = init_blinds(sb, bb, ante);
-    let mut game_state = init_game_state(players, blinds);
+// Hand histories are stamped with started_at, in seconds since the unix epoch
+pub fn play_game(config: &GameConfig, started_at: u64) -> io::Result<()> {
+    let mut session = match config.resume {
+        Some(ref path) => load_session(path)?,
+        None => new_session(config),
+    };
+    // Everyone's hole cards would give the game away to a human at the table
+    let show_hole_cards = session.humans.is_empty();
+    session
+        .game
+        .add_observer(Box::new(init_print_observer(show_hole_cards)));
+    let history = match config.history {
+        Some(ref path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            let writer = Rc::new(RefCell::new(init_history_writer(
+                file,
+                started_at,
+                session.humans.first().cloned(),
+            )));
+            session.game.add_observer(Box::new(writer.clone()));
+            Some(writer)
+        }
+        None => None,
+    };
+    let history_error = || match history {
+        Some(ref writer) => writer.borrow_mut().take_error(),
+        None => None,
+    };
 
-    game_state.play();
+    // A resumed session keeps saving over the file it came from
+    let save_path = config.save.as_ref().or(config.resume.as_ref());
+    let save = |session: &Session| match save_path {
+        Some(path) => session.save(path),
+        None => Ok(()),
+    };
+    save(&session)?;
+    match config.variant {
+        GameVariant::Holdem => {
+            while session.game.play_hand() {
+                if let Some(e) = history_error() {
+                    return Err(e);
+                }
+                save(&session)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn new_session(config: &GameConfig) -> Session {
+    let players = init_players(config.num_players, config.humans, config.starting_stack);
+    let humans = players
+        .iter()
+        .take(config.humans as usize)
+        .map(|p| p.id)
+        .collect();
+    let blinds = init_blinds_with_ante_mode(config.sb, config.bb, config.ante, config.ante_mode);
+    let mut game_state = match config.seed {
+        Some(seed) => init_seeded_game_state(players, blinds, seed),
+        None => init_game_state(players, blinds),
+    };
+    game_state.structure = config.structure;
+    init_session(game_state, humans)
 }
@@ -1,13 +1,20 @@
 use rand::{thread_rng, Rng};
 use std::collections::{HashMap, HashSet};
-use std::iter::Filter;
 
 use card::*;
 use deck::*;
-use hand::*;
+use evaluator::evaluate;
+use event::{GameEvent, GameObserver};
 use player::*;
+#[cfg(feature = "serde")]
+use serde::ser::{Error, Serializer};
+use strategy::{BetRange, GameView, SeatView};
 
+// With the serde feature a game can be saved and loaded, but only between hands does a round
+// trip play on exactly as the original would. The random stream isn't saved, so serializing a
+// hand in progress is an error.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GameState {
     // TODO: Could use table/seat abstraction instead of raw Player Vec.
     // Will probably be necessary to support MTTs & possibly proper dead button behavior.
@@ -15,6 +22,8 @@ pub struct GameState {
     // TODO: Genericize to support multiple games.
     // TODO: Consider putting players in Boxes
     pub players: PlayerVec,
+    // Seats the table started with, which stays put as players bust
+    pub table_size: usize,
     pub blinds: Blinds,
     pub pot: Pot,
     pub sidepots: Vec<Pot>,
@@ -23,26 +32,82 @@ pub struct GameState {
     pub small_blind: usize,
     pub big_blind: usize,
     pub player_to_act: usize,
+    // Highest street total so far
     pub current_bet: Option<u32>,
+    // Size of the last full bet or raise this street; raises must go up by at least this much
+    pub min_raise: u32,
+    // Street total the last full bet or raise went to. Short all-ins that together raise a full
+    // raise past it reopen the betting.
+    pub full_raise_to: u32,
+    // Full bets and raises this street, counting the big blind preflop
+    pub raises_this_street: u32,
     pub board: CardVec,
     pub street: Street,
+    pub structure: BettingStructure,
     pub hand_count: u32,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_between_hands"))]
+    pub hand_in_progress: bool,
+    // Seat the game is waiting on a decision from
+    pub awaiting: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub observers: Vec<Box<dyn GameObserver>>,
+    // Dealt from instead of a freshly shuffled deck at the start of the next hand
+    pub next_deck: Option<Deck>,
+    // The game stops once this many hands have been played
+    pub hand_limit: Option<u32>,
+    // Every shuffle and random choice in a session derives from this, so it can be replayed
+    pub seed: u64,
+    // Not saved, as the next hand reseeds it from the seed and hand count
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_rng"))]
+    pub rng: GameRng,
 }
 
-#[derive(Debug)]
+#[cfg(feature = "serde")]
+fn default_rng() -> GameRng {
+    seeded_rng(0)
+}
+
+#[cfg(feature = "serde")]
+fn serialize_between_hands<S: Serializer>(
+    hand_in_progress: &bool,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    if *hand_in_progress {
+        return Err(S::Error::custom("A game can only be saved between hands"));
+    }
+    s.serialize_bool(false)
+}
+
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 10;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Blinds {
     pub sb: u32,
     pub bb: u32,
     pub ante: Option<u32>,
+    pub ante_mode: AnteMode,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AnteMode {
+    // Every player dealt in posts the ante
+    PerPlayer,
+    // The big blind posts the ante for the whole table
+    BigBlind,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Pot {
     pub chips: u32,
     pub participants: HashSet<PlayerId>,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Street {
     PreFlop,
     Flop,
@@ -50,117 +115,356 @@ pub enum Street {
     River,
     Showdown,
 }
+
+// Together with the blinds this decides how much can be bet or raised
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BettingStructure {
+    NoLimit,
+    // Raises go up to the size of the pot after calling
+    PotLimit,
+    // Bets and raises are one big blind on the first two streets and two after that. At most
+    // `cap` bets and raises go in per street, unless the hand is down to two players.
+    FixedLimit { cap: u32 },
+}
+
+pub const DEFAULT_RAISE_CAP: u32 = 4;
+
+// How many illegal actions a strategy gets to try before the engine checks or folds for it
+pub const MAX_ATTEMPTS: u32 = 3;
+
+// A decision the game is waiting on, from the player at `seat`
+#[derive(Clone, Debug)]
+pub struct DecisionRequest {
+    pub seat: usize,
+    pub view: GameView,
+}
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum IllegalAction {
+    #[fail(display = "No player is being asked for a decision")]
+    NoDecisionPending,
+    #[fail(display = "Player {} isn't in the hand", _0)]
+    NotInHand(PlayerId),
+    #[fail(display = "Player {} is all in and has no decisions left", _0)]
+    AllIn(PlayerId),
+    #[fail(display = "Can't check facing a bet, {} to call", _0)]
+    CheckFacingBet(u32),
+    #[fail(display = "Nothing to call")]
+    NothingToCall,
+    #[fail(display = "Calling costs {} chips, not {}", expected, got)]
+    WrongCallAmount { expected: u32, got: u32 },
+    #[fail(display = "Can't bet facing a bet, raise instead")]
+    BetFacingBet,
+    #[fail(display = "Can't raise without a bet, bet instead")]
+    RaiseWithoutBet,
+    #[fail(display = "Betting isn't open to this player")]
+    BettingClosed,
+    #[fail(
+        display = "{} is outside the allowed range of {} to {}",
+        amount, min, max
+    )]
+    SizeOutOfRange { amount: u32, min: u32, max: u32 },
+}
 impl GameState {
+    // Runs the whole session, asking each seat's strategy for its decisions
     pub fn play(&mut self) {
-        // The top level game loop, abstracting one hand (termed round in the code) of poker.
-        // Loops while there is more than 1 player at the table, and any player with more than 0 chips
-        println!("Game starting");
-        while self.game_continuing() {
-            //plumbing
-            println!("Round {} starting", self.hand_count);
-            self.init_round();
-
-            // game logic
-
-            // round setup
-            self.rotate_button();
-            self.take_blinds();
-            self.deal_hands();
-
-            // playing round, until the showdown or one player remaining
-            'round: while self.round_continuing() {
-                self.advance_player_to_act();
-                if self.is_betting_done() {
-                    self.transition_street();
-                } else {
-                    if self.players[self.player_to_act].all_in {
-                        continue 'round;
-                    }
-                    let current_bet = self.current_bet.clone();
-                    let action = self.players[self.player_to_act]
-                        .announce_action(current_bet, self.blinds.bb);
-                    let id = self.players[self.player_to_act].id;
-                    self.apply_action(action, id);
+        while self.play_hand() {}
+    }
+
+    // Plays out the hand in progress, or deals and plays the next one, stopping between hands.
+    // False once the game is over.
+    pub fn play_hand(&mut self) -> bool {
+        if !self.hand_in_progress {
+            if !self.game_continuing() {
+                return false;
+            }
+            self.start_hand();
+        }
+        while self.hand_in_progress {
+            match self.awaiting {
+                Some(seat) => self.play_decision(seat),
+                None => self.advance_hand(),
+            }
+        }
+        true
+    }
+
+    fn play_decision(&mut self, seat: usize) {
+        let action = self.decide(seat);
+        // Only ever legal after decide, but never trust it blindly
+        if self.submit(action).is_err() {
+            let fallback = self.view_for(seat).fallback_action();
+            self.submit(fallback)
+                .expect("checking or folding is always open to the seat being asked");
+        }
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        for observer in &mut self.observers {
+            observer.on_event(&event);
+        }
+    }
+
+    fn stacks(&self) -> Vec<(PlayerId, u32)> {
+        self.players.iter().map(|p| (p.id, p.chips)).collect()
+    }
+
+    // Runs the game forward until a player has to decide something, and describes that decision.
+    // Calling it again before submitting returns the same request. None means the game is over.
+    pub fn next_decision(&mut self) -> Option<DecisionRequest> {
+        loop {
+            if let Some(seat) = self.awaiting {
+                return Some(DecisionRequest {
+                    seat,
+                    view: self.view_for(seat),
+                });
+            }
+
+            if !self.hand_in_progress {
+                // Loops while there is more than 1 player at the table, and any player with more
+                // than 0 chips
+                if !self.game_continuing() {
+                    return None;
                 }
+                self.start_hand();
+            } else {
+                self.advance_hand();
             }
+        }
+    }
+
+    // Moves the hand in progress on by one step, which may leave a decision pending
+    fn advance_hand(&mut self) {
+        if !self.round_continuing() {
+            // Showdown or one player remaining
+            self.finish_hand();
+        } else {
+            self.advance_player_to_act();
+            if self.is_betting_done() {
+                self.transition_street();
+            } else if !self.players[self.player_to_act].all_in {
+                self.awaiting = Some(self.player_to_act);
+            }
+        }
+    }
+
+    // Carries out the pending decision. An illegal action leaves it pending.
+    pub fn submit(&mut self, action: PlayerAction) -> Result<PlayerAction, IllegalAction> {
+        let seat = self.awaiting.ok_or(IllegalAction::NoDecisionPending)?;
+        let action = self.take_action(seat, action)?;
+        self.awaiting = None;
+        Ok(action)
+    }
+
+    fn start_hand(&mut self) {
+        if self.hand_count == 0 {
+            let seed = self.seed;
+            self.emit(GameEvent::GameStarted { seed });
+        }
+        //plumbing
+        self.init_round();
+
+        // round setup
+        self.rotate_button();
+        let event = GameEvent::HandStarted {
+            hand_number: self.hand_count,
+            table_size: self.table_size,
+            button: self.players[self.button].id,
+            small_blind: self.players[self.small_blind].id,
+            big_blind: self.players[self.big_blind].id,
+            blinds: self.blinds,
+            structure: self.structure,
+            seats: self
+                .players
+                .iter()
+                .map(|p| (p.id, p.seat, p.name.clone(), p.chips))
+                .collect(),
+        };
+        self.emit(event);
+        self.take_antes();
+        self.take_blinds();
+        self.deal_hands();
+        self.hand_in_progress = true;
+    }
 
-            self.award_pots();
-            // plumbing
-            self.end_round();
+    fn finish_hand(&mut self) {
+        if self.street == Street::Showdown {
+            self.show_hands();
         }
+        self.award_pots();
+        let event = GameEvent::HandFinished {
+            hand_number: self.hand_count,
+            stacks: self.stacks(),
+        };
+        self.emit(event);
+        // plumbing
+        self.end_round();
+        self.hand_in_progress = false;
 
-        println!("Game over! Player {} wins", self.players[0].id);
+        if !self.game_continuing() {
+            let winner = if self.players.len() == 1 {
+                Some(self.players[0].id)
+            } else {
+                None
+            };
+            self.emit(GameEvent::GameOver { winner });
+        }
     }
 
     // Plumbing
     // Reset ephemeral round state (excluding player idxs), increment hand counter
     fn init_round(&mut self) {
-        self.pot = init_pot();
-        self.deck = init_shuffled_deck();
         self.hand_count += 1;
+        self.rng = hand_rng(self.seed, self.hand_count);
+        self.pot = init_pot();
+        self.deck = match self.next_deck.take() {
+            Some(deck) => deck,
+            None => init_shuffled_deck_with(&mut self.rng),
+        };
         self.street = Street::PreFlop;
         self.sidepots = vec![];
         self.board = vec![];
 
         for ref mut player in &mut self.players {
             player.init_for_round();
+            player.strategy.new_hand(self.rng.gen());
         }
     }
 
     fn end_round(&mut self) {
-        self.players = self.players
+        // Keep the button on the same player across removals. If they busted, it stays with the
+        // nearest survivor before them so rotating still hands it to the next player in order.
+        let seats = self.players.len();
+        let mut button_id = (0..seats)
+            .map(|i| &self.players[(self.button + seats - i) % seats])
+            .find(|p| p.chips > 0)
+            .map(|p| p.id);
+
+        // Going heads up the big blind moves on to the next live player and the button is seated
+        // from that, so nobody posts the big blind twice in a row. Leaving the button on the new
+        // big blind's seat makes rotating hand it to the other player.
+        let survivors = self.players.iter().filter(|p| p.chips > 0).count();
+        if survivors == 2 && seats > 2 {
+            button_id = (1..seats)
+                .map(|i| &self.players[(self.big_blind + i) % seats])
+                .find(|p| p.chips > 0)
+                .map(|p| p.id);
+        }
+
+        let busted: Vec<PlayerId> = self
+            .players
             .iter()
-            .cloned()
-            .filter(|p| p.chips > 0)
+            .filter(|p| p.chips == 0)
+            .map(|p| p.id)
             .collect();
+        for player in busted {
+            self.emit(GameEvent::PlayerEliminated { player });
+        }
+
+        self.players.retain(|p| p.chips > 0);
+        self.button = button_id
+            .and_then(|id| self.players.iter().position(|p| p.id == id))
+            .unwrap_or(0);
     }
 
     // One function to both rotate button and calc sb/bb/player_to_act as they are order dependant
-    // TODO: Make this not terrible.
-    // TODO: Handle heads up
+    // player_to_act is left on the last seat to have acted, so the loop in play advances it to
+    // the first player to act.
     fn rotate_button(&mut self) {
-        println!("Rotating button");
-        self.button = match self.button {
-            n if n + 1 >= self.players.len() => 0,
-            _ => self.button + 1,
-        };
-        println!("Button at idx {}", self.button);
-
-        self.small_blind = match self.button {
-            n if n + 1 >= self.players.len() => 0,
-            _ => self.button + 1,
-        };
-        println!("SB at idx {}", self.small_blind);
+        self.button = self.next_seat(self.button);
 
-        self.big_blind = match self.small_blind {
-            n if n + 1 >= self.players.len() => 0,
-            _ => self.small_blind + 1,
+        // Heads up the button posts the small blind and acts first preflop
+        self.small_blind = if self.players.len() == 2 {
+            self.button
+        } else {
+            self.next_seat(self.button)
         };
-        println!("BB at idx {}", self.big_blind);
+        self.big_blind = self.next_seat(self.small_blind);
+        self.player_to_act = self.big_blind;
+    }
 
-        self.player_to_act = match self.big_blind {
-            n if n + 1 >= self.players.len() => 0,
-            _ => self.big_blind + 1,
-        };
-        println!("PTA at idx {}", self.player_to_act);
+    fn next_seat(&self, idx: usize) -> usize {
+        if idx + 1 >= self.players.len() {
+            0
+        } else {
+            idx + 1
+        }
     }
 
     fn deal_hands(&mut self) {
+        let mut events = vec![];
         for ref mut player in &mut self.players {
-            player.hole_cards = Some(self.deck.deal_cards(2));
-            println!("Dealt {:?} to player {}", player.hole_cards, player.id);
+            let cards = self.deck.deal_cards(2);
+            player.hole_cards = Some(cards.clone());
+            events.push(GameEvent::HoleCardsDealt {
+                player: player.id,
+                cards,
+            });
+        }
+        for event in events {
+            self.emit(event);
         }
     }
 
-    // TODO: Make blinds occur as betting actions
+    // Antes go into the pot without counting towards the bet to call. A player can be put all in
+    // by the ante alone, which limits them to the pots it reaches.
+    fn take_antes(&mut self) {
+        let ante = match self.blinds.ante {
+            Some(n) if n > 0 => n,
+            _ => return,
+        };
+
+        let mut events = vec![];
+        match self.blinds.ante_mode {
+            AnteMode::PerPlayer => {
+                for player in &mut self.players {
+                    let amount = player.give_ante(ante);
+                    events.push(GameEvent::AntePosted {
+                        player: player.id,
+                        amount,
+                    });
+                }
+            }
+            AnteMode::BigBlind => {
+                // When the big blind can't cover both, the blind is posted first
+                let bb = self.blinds.bb;
+                let player = &mut self.players[self.big_blind];
+                let ante = ante.min(player.chips.saturating_sub(bb));
+                if ante > 0 {
+                    let amount = player.give_ante(ante);
+                    events.push(GameEvent::AntePosted {
+                        player: player.id,
+                        amount,
+                    });
+                }
+            }
+        }
+        for event in events {
+            self.emit(event);
+        }
+        self.build_pots();
+    }
+
+    // The bet to call is the full big blind even when the big blind is short
     fn take_blinds(&mut self) {
-        let action = self.players[self.small_blind].give_blinds(self.blinds.sb);
-        let id = self.players[self.small_blind].id;
-        self.apply_action(action, id);
+        let amount = self.players[self.small_blind].give_blinds(self.blinds.sb);
+        let player = self.players[self.small_blind].id;
+        self.emit(GameEvent::SmallBlindPosted { player, amount });
 
-        let action = self.players[self.big_blind].give_blinds(self.blinds.bb);
-        let id = self.players[self.big_blind].id;
-        self.apply_action(action, id);
+        let amount = self.players[self.big_blind].give_blinds(self.blinds.bb);
+        let player = self.players[self.big_blind].id;
+        self.emit(GameEvent::BigBlindPosted { player, amount });
+
+        self.current_bet = Some(self.blinds.bb);
+        self.min_raise = self.blinds.bb;
+        self.full_raise_to = self.blinds.bb;
+        self.raises_this_street = 1;
+        self.build_pots();
     }
 
     // More of this bad pattern
@@ -181,196 +485,406 @@ impl GameState {
         // plumbing
         for ref mut player in &mut self.players {
             player.last_action = None;
+            player.street_committed = 0;
+            player.acted_since_full_raise = false;
         }
 
         // gamelogic
         self.current_bet = None;
+        self.full_raise_to = 0;
+        self.raises_this_street = 0;
         self.reset_player_to_act();
-        match self.street {
-            Street::PreFlop => {
-                self.board.append(&mut self.deck.deal_cards(3));
-                self.street = Street::Flop;
-                println!("Flop: {:?}", self.board);
-            }
-            Street::Flop => {
-                self.board.append(&mut self.deck.deal_cards(1));
-                self.street = Street::Turn;
-                println!("Turn: {:?}", self.board);
-            }
-            Street::Turn => {
-                self.board.append(&mut self.deck.deal_cards(1));
-                self.street = Street::River;
-                println!("River: {:?}", self.board);
-            }
-            Street::River => {
-                self.street = Street::Showdown;
-                println!("Showdown");
-            }
-            Street::Showdown => {
-                panic!("This ain't suppposed to happen");
-            }
+        let (street, cards) = match self.street {
+            Street::PreFlop => (Street::Flop, self.deck.deal_cards(3)),
+            Street::Flop => (Street::Turn, self.deck.deal_cards(1)),
+            Street::Turn => (Street::River, self.deck.deal_cards(1)),
+            Street::River => (Street::Showdown, vec![]),
+            // Nothing left to deal
+            Street::Showdown => return,
+        };
+        self.street = street;
+        self.board.extend(cards.iter().cloned());
+        self.min_raise = self.bet_size();
+
+        let board = self.board.clone();
+        self.emit(GameEvent::StreetDealt {
+            street,
+            cards,
+            board,
+        });
+    }
+
+    // Smallest bet on the current street, and the only size in fixed limit
+    fn bet_size(&self) -> u32 {
+        match (self.structure, self.street) {
+            (BettingStructure::FixedLimit { .. }, Street::Turn)
+            | (BettingStructure::FixedLimit { .. }, Street::River) => self.blinds.bb * 2,
+            _ => self.blinds.bb,
         }
     }
 
+    // Postflop the first player in hand after the button acts first, which heads up is the big
+    // blind. The loop in play advances from the button to them.
     fn reset_player_to_act(&mut self) {
-        self.player_to_act = self.button + 1;
+        self.player_to_act = self.button;
+    }
 
-        loop {
-            if self.player_to_act >= self.players.len() {
-                self.player_to_act = 0;
+    // Actions on offer to the player at idx, with the sizes a bet or raise can be made to
+    fn legal_actions(&self, idx: usize) -> (Vec<PlayerAction>, Option<BetRange>) {
+        let player = &self.players[idx];
+        let current_bet = self.current_bet.unwrap_or(0);
+        let to_call = current_bet.saturating_sub(player.street_committed);
+        let all_in_to = player.street_committed + player.chips;
+
+        // Folding is always allowed, even when checking would cost nothing
+        let mut actions = vec![];
+        if to_call == 0 {
+            actions.push(PlayerAction::Check);
+        } else {
+            actions.push(PlayerAction::Call(to_call.min(player.chips)));
+        }
+        actions.push(PlayerAction::Fold);
+
+        // Raising is pointless when nobody else can call, and after acting a player can only
+        // raise again once somebody makes a full raise
+        let others_can_act = self
+            .players
+            .iter()
+            .enumerate()
+            .any(|(i, p)| i != idx && p.in_hand && !p.all_in);
+        let reopened = !player.acted_since_full_raise;
+        let capped = match self.structure {
+            BettingStructure::FixedLimit { cap } => {
+                self.raises_this_street >= cap && self.num_hand_participants() > 2
             }
+            _ => false,
+        };
+        let mut bet_range = None;
+        if all_in_to > current_bet && others_can_act && reopened && !capped {
+            let (min, max) = match self.structure {
+                BettingStructure::NoLimit => (current_bet + self.min_raise, all_in_to),
+                BettingStructure::PotLimit => {
+                    let pot: u32 = self.players.iter().map(|p| p.committed).sum();
+                    (current_bet + self.min_raise, current_bet + pot + to_call)
+                }
+                BettingStructure::FixedLimit { .. } => {
+                    let to = current_bet + self.bet_size();
+                    (to, to)
+                }
+            };
+            let min = min.min(all_in_to);
+            bet_range = Some(BetRange {
+                min,
+                max: max.min(all_in_to),
+            });
+            actions.push(match self.current_bet {
+                Some(_) => PlayerAction::Raise(min),
+                None => PlayerAction::Bet(min),
+            });
+        }
 
-            if self.players[self.player_to_act].in_hand {
-                break;
+        (actions, bet_range)
+    }
+
+    // Read only snapshot of the table from the point of view of the player at idx
+    pub fn view_for(&self, idx: usize) -> GameView {
+        let player = &self.players[idx];
+        let (legal_actions, bet_range) = self.legal_actions(idx);
+        GameView {
+            player_id: player.id,
+            hole_cards: player.hole_cards.clone(),
+            board: self.board.clone(),
+            street: self.street,
+            pot: self.pot_total(),
+            current_bet: self.current_bet,
+            to_call: self
+                .current_bet
+                .unwrap_or(0)
+                .saturating_sub(player.street_committed)
+                .min(player.chips),
+            big_blind: self.blinds.bb,
+            seats: self
+                .players
+                .iter()
+                .map(|p| SeatView {
+                    id: p.id,
+                    name: p.name.clone(),
+                    chips: p.chips,
+                    committed: p.committed,
+                    street_committed: p.street_committed,
+                    in_hand: p.in_hand,
+                    all_in: p.all_in,
+                })
+                .collect(),
+            legal_actions,
+            bet_range,
+        }
+    }
+
+    // Asks the strategy at idx for an action, letting it retry when it picks an illegal one
+    fn decide(&mut self, idx: usize) -> PlayerAction {
+        let view = self.view_for(idx);
+        for _ in 0..MAX_ATTEMPTS {
+            let action = self.players[idx].strategy.decide(&view);
+            match view.validate(action) {
+                Ok(()) => return action,
+                Err(e) => {
+                    self.emit(GameEvent::ActionRejected {
+                        player: view.player_id,
+                        action,
+                        reason: e.to_string(),
+                    });
+                    self.players[idx].strategy.rejected(action, &e);
+                }
             }
+        }
+        self.view_for(idx).fallback_action()
+    }
 
-            self.player_to_act += 1;
+    // Validates an action for the player at idx and carries it out, returning what actually
+    // happened. Illegal actions leave the game untouched.
+    fn take_action(
+        &mut self,
+        idx: usize,
+        action: PlayerAction,
+    ) -> Result<PlayerAction, IllegalAction> {
+        let player = &self.players[idx];
+        if !player.in_hand {
+            return Err(IllegalAction::NotInHand(player.id));
         }
+        if player.all_in {
+            return Err(IllegalAction::AllIn(player.id));
+        }
+        self.view_for(idx).validate(action)?;
+
+        let action = self.players[idx].perform(action);
+        let id = self.players[idx].id;
+        self.apply_action(action, id);
+        Ok(action)
     }
 
     fn apply_action(&mut self, action: PlayerAction, id: u32) {
         match action {
-            PlayerAction::Bet(bet) => {
-                println!("Player {} bets {} chips", id, bet);
-                self.pot.chips += bet;
-                self.pot.participants.insert(id);
-                self.current_bet = Some(bet);
-            }
-            PlayerAction::Raise(bet) => {
-                println!("Player {} raises to {} chips", id, bet);
-                self.pot.chips += bet;
-                self.pot.participants.insert(id);
-                self.current_bet = Some(bet);
-            }
-            PlayerAction::Call(bet) => {
-                println!("Player {} calls {} chips", id, bet);
-                self.pot.chips += bet;
-                self.pot.participants.insert(id);
-                self.current_bet = Some(bet);
+            PlayerAction::Bet(to) | PlayerAction::Raise(to) => self.raise_to(to, id),
+            PlayerAction::Call(_) | PlayerAction::Check | PlayerAction::Fold => (),
+        }
+        self.build_pots();
+
+        let all_in = self.players.iter().any(|p| p.id == id && p.all_in);
+        self.emit(GameEvent::ActionTaken {
+            player: id,
+            action,
+            all_in,
+        });
+    }
+
+    // Only a full raise resets the minimum raise and reopens the betting to everyone else. After
+    // an all-in for less, players who already acted can call or fold but not raise again, until
+    // short all-ins add up to a full raise over the last one.
+    fn raise_to(&mut self, to: u32, id: PlayerId) {
+        let current_bet = self.current_bet.unwrap_or(0);
+        if to <= current_bet {
+            return;
+        }
+        if to - current_bet >= self.min_raise {
+            self.min_raise = to - current_bet;
+        }
+        if to - self.full_raise_to >= self.min_raise {
+            self.full_raise_to = to;
+            self.raises_this_street += 1;
+            for player in self.players.iter_mut().filter(|p| p.id != id) {
+                player.acted_since_full_raise = false;
             }
-            PlayerAction::Check => {
-                println!("Player {} checks", id);
+        }
+        self.current_bet = Some(to);
+    }
+
+    // Rebuild the main pot and side pots from what each player has committed this hand.
+    // Pots are layered at every distinct all-in amount; folded players' chips stay in the pots
+    // they reached, but only players still in the hand are eligible to win them.
+    fn build_pots(&mut self) {
+        let mut levels: Vec<u32> = self
+            .players
+            .iter()
+            .filter(|p| p.in_hand && p.all_in)
+            .map(|p| p.committed)
+            .collect();
+        levels.push(self.players.iter().map(|p| p.committed).max().unwrap_or(0));
+        levels.sort();
+        levels.dedup();
+
+        let mut pots: Vec<Pot> = vec![];
+        let mut floor = 0;
+        let mut carried = 0;
+        for level in levels {
+            let chips: u32 = self
+                .players
+                .iter()
+                .map(|p| p.committed.min(level).saturating_sub(floor))
+                .sum();
+            let participants: HashSet<PlayerId> = self
+                .players
+                .iter()
+                .filter(|p| p.in_hand && p.committed >= level)
+                .map(|p| p.id)
+                .collect();
+            floor = level;
+
+            if chips == 0 {
+                continue;
             }
-            PlayerAction::Fold => {
-                println!("Player {} folds", id);
+            // Chips nobody live can win (folded players out-committing everyone) go to the pot
+            // below, or into the next pot up when there's nothing below
+            if participants.is_empty() {
+                match pots.last_mut() {
+                    Some(pot) => pot.chips += chips,
+                    None => carried += chips,
+                }
+            } else {
+                pots.push(Pot {
+                    chips: chips + carried,
+                    participants,
+                });
+                carried = 0;
             }
         }
+        if carried > 0 {
+            pots.push(Pot {
+                chips: carried,
+                participants: self
+                    .players
+                    .iter()
+                    .filter(|p| p.in_hand)
+                    .map(|p| p.id)
+                    .collect(),
+            });
+        }
+
+        let mut pots = pots.into_iter();
+        self.pot = pots.next().unwrap_or_else(init_pot);
+        self.sidepots = pots.collect();
     }
 
     fn award_pots(&mut self) {
-        if Street::Showdown == self.street {
-            // Whats the idiomatic rust way to create a vec from a vec and an item?
-            self.sidepots.push(self.pot.clone());
-            for mut pot in &self.sidepots {
-                let winner_ids = &self.determine_pot_winners(pot.participants.clone());
-                println!("Winners are: {:?}", winner_ids);
-                let chop = winner_ids.len() as u32;
-                let chips = (pot.chips - (pot.chips % chop)) / chop;
-                let remainder = pot.chips % chop;
-                let remainder_winner = thread_rng().choose(winner_ids).unwrap();
-
-                for id in winner_ids {
-                    let (winner_idx, _) = self.players
-                        .iter()
-                        .enumerate()
-                        .filter(|&(i, p)| p.in_hand && p.id == *id)
-                        .next()
-                        .expect("Award pots: showdown");
-                    println!(
-                        "Player {} is a winner, receiving {} chips",
-                        self.players[winner_idx].id, chips
-                    );
-                    self.players[winner_idx].receive_chips(chips);
-                    if id == remainder_winner {
-                        self.players[winner_idx].receive_chips(remainder);
-                    }
+        self.build_pots();
+        let mut pots = vec![self.pot.clone()];
+        pots.extend(self.sidepots.iter().cloned());
+
+        for (pot_number, pot) in pots.into_iter().enumerate() {
+            let mut winner_ids = if pot.participants.len() > 1 {
+                self.determine_pot_winners(pot.participants.clone())
+            } else {
+                pot.participants.iter().cloned().collect()
+            };
+            // Sets iterate in arbitrary order; sort so the odd chip draw is reproducible
+            winner_ids.sort();
+            if winner_ids.is_empty() {
+                continue;
+            }
+
+            let chop = winner_ids.len() as u32;
+            let chips = (pot.chips - (pot.chips % chop)) / chop;
+            let remainder = pot.chips % chop;
+            let remainder_winner = self.rng.choose(&winner_ids).cloned();
+
+            for id in winner_ids {
+                let amount = if Some(id) == remainder_winner {
+                    chips + remainder
+                } else {
+                    chips
+                };
+                if let Some(player) = self.players.iter_mut().find(|p| p.in_hand && p.id == id) {
+                    player.receive_chips(amount);
                 }
+                self.emit(GameEvent::PotAwarded {
+                    pot: pot_number,
+                    player: id,
+                    amount,
+                });
             }
-        } else {
-            let (winner_idx, _) = self.players
-                .iter()
-                .enumerate()
-                .filter(|&(i, p)| p.in_hand)
-                .next()
-                .expect("Award pots: pre-showdown branch, no winner!");
-            self.players[winner_idx].receive_chips(self.pot.chips);
         }
     }
 
     // Utils
+    // Chips across the main pot and all side pots
+    pub fn pot_total(&self) -> u32 {
+        self.pot.chips + self.sidepots.iter().map(|p| p.chips).sum::<u32>()
+    }
+
     fn game_continuing(&self) -> bool {
         // This redundancy should allow for players to run out of chips but not leave the game
-        self.players.len() > 1 && self.num_players_with_chips() > 0
+        self.players.len() > 1
+            && self.num_players_with_chips() > 0
+            && self.hand_limit.is_none_or(|limit| self.hand_count < limit)
     }
 
     fn round_continuing(&self) -> bool {
         self.num_hand_participants() > 1 && self.street != Street::Showdown
     }
 
-    // This is pretty damn convoluted
-    fn is_betting_done(&mut self) -> bool {
-        if self.street == Street::PreFlop && self.big_blind == self.player_to_act
-            && self.players[self.big_blind].last_action.unwrap()
-                == PlayerAction::Bet(self.blinds.bb)
-        {
-            println!("Preflop, bb is pta, gs debug: {:?}", self);
-            return false;
-        }
+    // The street closes once everyone who can still bet has acted since the last full raise and
+    // matched the current bet. A lone player with chips behind who owes nothing has no one left
+    // to bet against, so the street closes without them.
+    fn is_betting_done(&self) -> bool {
+        let current_bet = self.current_bet.unwrap_or(0);
+        let can_act: Vec<&Player> = self
+            .players
+            .iter()
+            .filter(|p| p.in_hand && !p.all_in)
+            .collect();
 
-        if self.players
+        let lone = can_act.len() <= 1;
+        can_act
             .iter()
-            .filter(|p| p.in_hand)
-            .all(|p| Some(PlayerAction::Check) == p.last_action || p.all_in)
-        {
-            return true;
-        } else if self.current_bet.is_none() {
-            return false;
-        }
+            .all(|p| (lone || p.acted_since_full_raise) && p.street_committed >= current_bet)
+    }
 
-        // Safe to unwrap it because of early return above
-        let current_bet = self.current_bet.unwrap();
-        self.players
+    fn show_hands(&mut self) {
+        let events: Vec<GameEvent> = self
+            .players
             .iter()
             .filter(|p| p.in_hand)
-            .all(|p| match p.last_action {
-                Some(PlayerAction::Bet(bet))
-                | Some(PlayerAction::Call(bet))
-                | Some(PlayerAction::Raise(bet)) if bet == current_bet =>
-                {
-                    true
-                }
-                _ => false,
+            .filter_map(|p| {
+                p.hole_cards.as_ref().map(|cards| {
+                    let mut all_cards = self.board.clone();
+                    all_cards.extend(cards.iter().cloned());
+                    GameEvent::HandShown {
+                        player: p.id,
+                        cards: cards.clone(),
+                        rank: evaluate(&all_cards).hand_rank(),
+                    }
+                })
             })
+            .collect();
+        for event in events {
+            self.emit(event);
+        }
     }
 
     // yikes
     fn determine_pot_winners(&self, participants: HashSet<PlayerId>) -> Vec<PlayerId> {
-        println!("Determining pot winners");
         let mut player_hand_map = HashMap::new();
-        let mut hands = vec![];
-        let mut board = self.board.clone();
+        let board = self.board.clone();
         let mut winners = vec![];
 
         for id in participants {
-            if let Some(player) = self.players
-                .iter()
-                .filter(|p| p.in_hand && p.id == id)
-                .next()
-            {
-                let mut hole_cards: CardVec = player.hole_cards.clone().unwrap();
+            if let Some(player) = self.players.iter().find(|p| p.in_hand && p.id == id) {
+                let mut hole_cards: CardVec = match player.hole_cards {
+                    Some(ref cards) => cards.clone(),
+                    None => continue,
+                };
                 let mut all_cards = board.clone();
                 all_cards.append(&mut hole_cards);
-                let mut players_best_hand = find_best_hand(all_cards);
-                println!("Player {}s best hand: {:?}", id, players_best_hand);
-                player_hand_map.insert(id, players_best_hand.clone());
-                hands.push(players_best_hand);
+                player_hand_map.insert(id, evaluate(&all_cards));
             }
         }
 
-        hands.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        let best = hands.iter().last().unwrap();
+        let best = match player_hand_map.values().max() {
+            Some(best) => *best,
+            None => return winners,
+        };
         player_hand_map.iter().for_each(|(id, hand)| {
-            if hand == best {
+            if *hand == best {
                 winners.push(*id);
             }
         });
@@ -387,21 +901,31 @@ impl GameState {
     }
 }
 
-pub fn init_game_state(mut players: Vec<Player>, blinds: Blinds) -> GameState {
+pub fn init_game_state(players: Vec<Player>, blinds: Blinds) -> GameState {
+    init_seeded_game_state(players, blinds, thread_rng().gen())
+}
+
+pub fn init_seeded_game_state(mut players: Vec<Player>, blinds: Blinds, seed: u64) -> GameState {
     let player_count = players.len();
-    if player_count > 10 || player_count < 2 {
+    if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&player_count) {
         panic!(
-            "Attmpting to init game with {} players; must be between 2 and 10",
-            players.len()
+            "Attmpting to init game with {} players; must be between {} and {}",
+            players.len(),
+            MIN_PLAYERS,
+            MAX_PLAYERS
         );
     }
 
     // TODO: Separate player ordering logic
     // wiring off for debug purposes
     // thread_rng().shuffle(&mut players);
+    for (seat, player) in players.iter_mut().enumerate() {
+        player.seat = seat;
+    }
 
     GameState {
         players,
+        table_size: player_count,
         blinds,
         pot: init_pot(),
         sidepots: vec![],
@@ -412,14 +936,39 @@ pub fn init_game_state(mut players: Vec<Player>, blinds: Blinds) -> GameState {
         big_blind: 2,
         player_to_act: 3,
         current_bet: None,
+        min_raise: 0,
+        full_raise_to: 0,
+        raises_this_street: 0,
         board: vec![],
         street: Street::PreFlop,
+        structure: BettingStructure::NoLimit,
         hand_count: 0,
+        hand_in_progress: false,
+        awaiting: None,
+        observers: vec![],
+        next_deck: None,
+        hand_limit: None,
+        seed,
+        rng: seeded_rng(seed),
     }
 }
 
 pub fn init_blinds(sb: u32, bb: u32, ante: Option<u32>) -> Blinds {
-    Blinds { sb, bb, ante }
+    init_blinds_with_ante_mode(sb, bb, ante, AnteMode::PerPlayer)
+}
+
+pub fn init_blinds_with_ante_mode(
+    sb: u32,
+    bb: u32,
+    ante: Option<u32>,
+    ante_mode: AnteMode,
+) -> Blinds {
+    Blinds {
+        sb,
+        bb,
+        ante,
+        ante_mode,
+    }
 }
 
 pub fn init_pot() -> Pot {
@@ -432,11 +981,43 @@ pub fn init_pot() -> Pot {
 #[cfg(test)]
 mod game_tests {
     use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::mpsc;
+    use strategy::{CallingStation, Strategy};
+
+    // Calls everything down, remembering every view it was shown
+    #[derive(Debug)]
+    struct Recorder(Rc<RefCell<Vec<GameView>>>);
+
+    impl Strategy for Recorder {
+        fn decide(&mut self, view: &GameView) -> PlayerAction {
+            self.0.borrow_mut().push(view.clone());
+            CallingStation.decide(view)
+        }
+    }
 
     fn get_n_dummy_players(n: u32) -> Vec<Player> {
         (0..n).map(|i| init_player(i, "Dummy", 100)).collect()
     }
 
+    // (committed, in_hand, all_in) per player
+    fn game_with_commitments(commitments: &[(u32, bool, bool)]) -> GameState {
+        let players = get_n_dummy_players(commitments.len() as u32);
+        let mut game = init_game_state(players, init_blinds(5, 10, None));
+        for (player, &(committed, in_hand, all_in)) in game.players.iter_mut().zip(commitments) {
+            player.committed = committed;
+            player.in_hand = in_hand;
+            player.all_in = all_in;
+        }
+        game.build_pots();
+        game
+    }
+
+    fn ids(ids: &[PlayerId]) -> HashSet<PlayerId> {
+        ids.iter().cloned().collect()
+    }
+
     #[test]
     fn it_inits_a_game() {
         let players = get_n_dummy_players(6);
@@ -469,4 +1050,854 @@ mod game_tests {
         init_game_state(players, blinds);
     }
 
+    #[test]
+    fn it_replays_a_seeded_session() {
+        let mut game_1 =
+            init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, None), 99);
+        let mut game_2 =
+            init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, None), 99);
+        game_1.play();
+        game_2.play();
+
+        let stacks = |game: &GameState| -> Vec<(PlayerId, u32)> {
+            game.players.iter().map(|p| (p.id, p.chips)).collect()
+        };
+        assert_eq!(game_1.hand_count, game_2.hand_count);
+        assert_eq!(stacks(&game_1), stacks(&game_2));
+    }
+
+    #[test]
+    fn it_asks_each_seats_strategy_for_actions() {
+        let views = Rc::new(RefCell::new(vec![]));
+        let mut players = get_n_dummy_players(2);
+        players.push(init_player_with_strategy(
+            2,
+            "Recorder",
+            100,
+            Box::new(Recorder(views.clone())),
+        ));
+        let mut game = init_seeded_game_state(players, init_blinds(5, 10, None), 5);
+        game.play();
+
+        let views = views.borrow();
+        assert!(!views.is_empty());
+        for view in views.iter() {
+            assert_eq!(view.player_id, 2);
+            assert_eq!(view.hole_cards.as_ref().map(|h| h.len()), Some(2));
+            assert_eq!(
+                view.seats.iter().find(|s| s.id == 2).map(|s| s.in_hand),
+                Some(true)
+            );
+            assert!(!view.legal_actions.is_empty());
+        }
+    }
+
+    // Runs round setup up to the first decision. With three fresh players the button moves to
+    // idx 1, so idx 2 posts the small blind and idx 0 the big blind.
+    fn set_up_hand(blinds: Blinds, stacks: &[u32]) -> GameState {
+        let mut game = init_seeded_game_state(get_n_dummy_players(stacks.len() as u32), blinds, 5);
+        game.init_round();
+        for (player, &chips) in game.players.iter_mut().zip(stacks) {
+            player.chips = chips;
+        }
+        game.rotate_button();
+        game.take_antes();
+        game.take_blinds();
+        game
+    }
+
+    #[test]
+    fn it_collects_an_ante_from_every_player() {
+        let game = set_up_hand(init_blinds(5, 10, Some(2)), &[100, 100, 100]);
+
+        assert_eq!(game.pot.chips, 21);
+        assert_eq!(
+            game.players.iter().map(|p| p.chips).collect::<Vec<u32>>(),
+            vec![88, 98, 93]
+        );
+        assert_eq!(game.current_bet, Some(10));
+        assert_eq!(game.players[1].last_action, None);
+    }
+
+    #[test]
+    fn it_collects_a_big_blind_ante() {
+        let blinds = init_blinds_with_ante_mode(5, 10, Some(10), AnteMode::BigBlind);
+        let game = set_up_hand(blinds, &[100, 100, 100]);
+
+        assert_eq!(game.pot.chips, 25);
+        assert_eq!(game.players[0].chips, 80);
+        assert_eq!(game.players[1].chips, 100);
+
+        // A short big blind covers the blind before the ante
+        let blinds = init_blinds_with_ante_mode(5, 10, Some(10), AnteMode::BigBlind);
+        let game = set_up_hand(blinds, &[14, 100, 100]);
+
+        assert_eq!(game.players[0].committed, 14);
+        assert_eq!(game.players[0].street_committed, 10);
+        assert!(game.players[0].all_in);
+    }
+
+    #[test]
+    fn it_limits_players_all_in_from_the_ante() {
+        let game = set_up_hand(init_blinds(5, 10, Some(2)), &[100, 1, 3]);
+
+        // idx 1 is all in for a 1 chip ante; idx 2 antes 2 and is all in for its 1 chip blind
+        assert!(game.players[1].all_in && game.players[1].in_hand);
+        assert_eq!(game.players[1].last_action, None);
+        assert_eq!(game.pot.chips, 3);
+        assert_eq!(game.pot.participants, ids(&[0, 1, 2]));
+        assert_eq!(game.sidepots[0].chips, 4);
+        assert_eq!(game.sidepots[0].participants, ids(&[0, 2]));
+        assert_eq!(game.sidepots[1].chips, 9);
+        assert_eq!(game.sidepots[1].participants, ids(&[0]));
+    }
+
+    #[test]
+    fn it_plays_out_a_session_with_antes() {
+        for seed in 0..20 {
+            for &mode in &[AnteMode::PerPlayer, AnteMode::BigBlind] {
+                let blinds = init_blinds_with_ante_mode(5, 10, Some(5), mode);
+                let mut game = init_seeded_game_state(get_n_dummy_players(4), blinds, seed);
+                game.play();
+
+                // Every chip ends up somewhere
+                assert_eq!(game.players.iter().map(|p| p.chips).sum::<u32>(), 400);
+            }
+        }
+    }
+
+    fn act(game: &mut GameState, idx: usize, action: PlayerAction) {
+        game.take_action(idx, action).unwrap();
+    }
+
+    #[test]
+    fn it_offers_raises_of_at_least_the_last_full_raise() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[100, 100, 100]);
+        let view = game.view_for(1);
+
+        assert_eq!(view.to_call, 10);
+        assert_eq!(
+            view.legal_actions,
+            vec![
+                PlayerAction::Call(10),
+                PlayerAction::Fold,
+                PlayerAction::Raise(20)
+            ]
+        );
+        assert_eq!(view.bet_range, Some(BetRange { min: 20, max: 100 }));
+
+        act(&mut game, 1, PlayerAction::Raise(35));
+        let view = game.view_for(2);
+
+        assert_eq!(game.players[1].chips, 65);
+        assert_eq!(view.to_call, 30);
+        assert_eq!(view.bet_range, Some(BetRange { min: 60, max: 100 }));
+        assert!(view.is_legal(PlayerAction::Raise(80)));
+        assert!(!view.is_legal(PlayerAction::Raise(50)));
+
+        // The big blind keeps its option when everyone just calls
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[100, 100, 100]);
+        act(&mut game, 1, PlayerAction::Call(10));
+        act(&mut game, 2, PlayerAction::Call(5));
+        assert!(!game.is_betting_done());
+        assert_eq!(
+            game.view_for(0).legal_actions,
+            vec![
+                PlayerAction::Check,
+                PlayerAction::Fold,
+                PlayerAction::Raise(20)
+            ]
+        );
+        act(&mut game, 0, PlayerAction::Check);
+        assert!(game.is_betting_done());
+    }
+
+    #[test]
+    fn it_does_not_reopen_betting_after_a_short_all_in() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[100, 100, 45]);
+        act(&mut game, 1, PlayerAction::Raise(30));
+        // All in for 15 more, short of the 20 needed for a full raise
+        act(&mut game, 2, PlayerAction::Raise(45));
+
+        assert_eq!(game.current_bet, Some(45));
+        assert_eq!(game.min_raise, 20);
+
+        // The big blind hasn't acted yet, so it can still raise
+        let view = game.view_for(0);
+        assert_eq!(view.to_call, 35);
+        assert_eq!(view.bet_range, Some(BetRange { min: 65, max: 100 }));
+        act(&mut game, 0, PlayerAction::Call(35));
+
+        // The original raiser can only call the extra or fold
+        let view = game.view_for(1);
+        assert_eq!(
+            view.legal_actions,
+            vec![PlayerAction::Call(15), PlayerAction::Fold]
+        );
+        assert_eq!(view.bet_range, None);
+        act(&mut game, 1, PlayerAction::Call(15));
+        assert!(game.is_betting_done());
+    }
+
+    #[test]
+    fn it_reopens_betting_once_short_all_ins_add_up_to_a_full_raise() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[100, 45, 60, 100]);
+        act(&mut game, 0, PlayerAction::Raise(30));
+        // 15 over the raise, short of the 20 needed
+        act(&mut game, 1, PlayerAction::Raise(45));
+        assert_eq!(game.view_for(0).bet_range, None);
+
+        // Another 15 makes 30 over the last full raise, which reopens the betting
+        act(&mut game, 2, PlayerAction::Raise(60));
+        assert_eq!(game.current_bet, Some(60));
+        assert_eq!(game.min_raise, 20);
+        act(&mut game, 3, PlayerAction::Call(50));
+
+        let view = game.view_for(0);
+        assert_eq!(view.to_call, 30);
+        assert_eq!(view.bet_range, Some(BetRange { min: 80, max: 100 }));
+        act(&mut game, 0, PlayerAction::Raise(100));
+        assert!(!game.is_betting_done());
+    }
+
+    #[test]
+    fn it_closes_the_street_once_everyone_has_acted_and_matched() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[100, 100, 25]);
+        act(&mut game, 1, PlayerAction::Raise(40));
+
+        assert!(game.players[1].acted_since_full_raise);
+        assert!(!game.players[0].acted_since_full_raise);
+
+        // A partial call puts the small blind all in and out of the closing rule
+        act(&mut game, 2, PlayerAction::Call(20));
+        assert!(game.players[2].all_in);
+        assert_eq!(game.players[2].street_committed, 25);
+        assert!(!game.is_betting_done());
+
+        act(&mut game, 0, PlayerAction::Call(30));
+        assert!(game.is_betting_done());
+    }
+
+    #[test]
+    fn it_closes_the_street_when_nobody_is_left_to_bet_against() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[100, 100, 30]);
+        act(&mut game, 1, PlayerAction::Fold);
+        act(&mut game, 2, PlayerAction::Raise(30));
+
+        // The big blind still owes chips, so it has to act
+        assert!(!game.is_betting_done());
+        act(&mut game, 0, PlayerAction::Call(20));
+        assert!(game.is_betting_done());
+
+        // Postflop it has no one left to bet against
+        game.transition_street();
+        assert!(game.is_betting_done());
+    }
+
+    #[test]
+    fn it_reopens_betting_after_a_full_raise() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[100, 100, 100]);
+        act(&mut game, 1, PlayerAction::Raise(30));
+        act(&mut game, 2, PlayerAction::Raise(50));
+        act(&mut game, 0, PlayerAction::Fold);
+
+        let view = game.view_for(1);
+        assert_eq!(view.to_call, 20);
+        assert_eq!(view.bet_range, Some(BetRange { min: 70, max: 100 }));
+    }
+
+    #[test]
+    fn it_limits_pot_limit_raises_to_the_pot_after_calling() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[200, 200, 200]);
+        game.structure = BettingStructure::PotLimit;
+
+        // 15 in the pot, 10 to call makes 25 on top of the 10 bet
+        assert_eq!(
+            game.view_for(1).bet_range,
+            Some(BetRange { min: 20, max: 35 })
+        );
+        act(&mut game, 1, PlayerAction::Raise(35));
+        // 50 in the pot, the small blind calls 30 more and raises 80 on top of 35
+        assert_eq!(
+            game.view_for(2).bet_range,
+            Some(BetRange { min: 60, max: 115 })
+        );
+        act(&mut game, 2, PlayerAction::Call(30));
+        act(&mut game, 0, PlayerAction::Call(25));
+        game.transition_street();
+
+        assert_eq!(
+            game.view_for(0).bet_range,
+            Some(BetRange { min: 10, max: 105 })
+        );
+    }
+
+    #[test]
+    fn it_sizes_fixed_limit_bets_by_street() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[200, 200, 200]);
+        game.structure = BettingStructure::FixedLimit { cap: 4 };
+
+        assert_eq!(
+            game.view_for(1).bet_range,
+            Some(BetRange { min: 20, max: 20 })
+        );
+        game.transition_street();
+        assert_eq!(
+            game.view_for(0).bet_range,
+            Some(BetRange { min: 10, max: 10 })
+        );
+        game.transition_street();
+        assert_eq!(
+            game.view_for(0).legal_actions,
+            vec![
+                PlayerAction::Check,
+                PlayerAction::Fold,
+                PlayerAction::Bet(20)
+            ]
+        );
+        act(&mut game, 0, PlayerAction::Bet(20));
+        assert_eq!(
+            game.view_for(1).bet_range,
+            Some(BetRange { min: 40, max: 40 })
+        );
+    }
+
+    #[test]
+    fn it_caps_fixed_limit_raises_unless_heads_up() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[200, 200, 200]);
+        game.structure = BettingStructure::FixedLimit { cap: 4 };
+        act(&mut game, 1, PlayerAction::Raise(20));
+        act(&mut game, 2, PlayerAction::Raise(30));
+        act(&mut game, 0, PlayerAction::Raise(40));
+
+        assert_eq!(game.raises_this_street, 4);
+        assert_eq!(
+            game.view_for(1).legal_actions,
+            vec![PlayerAction::Call(20), PlayerAction::Fold]
+        );
+
+        // Once it's heads up the cap no longer applies
+        act(&mut game, 1, PlayerAction::Fold);
+        assert_eq!(
+            game.view_for(2).bet_range,
+            Some(BetRange { min: 50, max: 50 })
+        );
+    }
+
+    #[test]
+    fn it_plays_out_sessions_in_every_structure() {
+        let structures = [
+            BettingStructure::NoLimit,
+            BettingStructure::PotLimit,
+            BettingStructure::FixedLimit { cap: 4 },
+        ];
+        for seed in 0..10 {
+            for &structure in &structures {
+                let mut game =
+                    init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, None), seed);
+                game.structure = structure;
+                game.play();
+
+                assert_eq!(game.players.iter().map(|p| p.chips).sum::<u32>(), 400);
+            }
+        }
+    }
+
+    #[test]
+    fn it_rejects_illegal_actions_without_changing_the_game() {
+        let mut game = set_up_hand(init_blinds(5, 10, None), &[100, 100, 100]);
+        let illegal = |game: &mut GameState, idx, action| {
+            let chips: Vec<u32> = game.players.iter().map(|p| p.chips).collect();
+            let current_bet = game.current_bet;
+            let result = game.take_action(idx, action);
+            assert_eq!(
+                game.players.iter().map(|p| p.chips).collect::<Vec<u32>>(),
+                chips
+            );
+            assert_eq!(game.current_bet, current_bet);
+            result.unwrap_err()
+        };
+
+        assert_eq!(
+            illegal(&mut game, 1, PlayerAction::Check),
+            IllegalAction::CheckFacingBet(10)
+        );
+        assert_eq!(
+            illegal(&mut game, 1, PlayerAction::Bet(0)),
+            IllegalAction::BetFacingBet
+        );
+        assert_eq!(
+            illegal(&mut game, 1, PlayerAction::Call(4)),
+            IllegalAction::WrongCallAmount {
+                expected: 10,
+                got: 4,
+            }
+        );
+        assert_eq!(
+            illegal(&mut game, 1, PlayerAction::Raise(15)),
+            IllegalAction::SizeOutOfRange {
+                amount: 15,
+                min: 20,
+                max: 100,
+            }
+        );
+        // Folding when a check is free gives nothing away, so it's allowed
+        let view = game.view_for(0);
+        assert!(view.is_legal(PlayerAction::Fold));
+        assert!(view.legal_actions.contains(&PlayerAction::Fold));
+
+        act(&mut game, 1, PlayerAction::Fold);
+        assert_eq!(
+            illegal(&mut game, 1, PlayerAction::Call(10)),
+            IllegalAction::NotInHand(1)
+        );
+
+        game.transition_street();
+        assert_eq!(
+            illegal(&mut game, 0, PlayerAction::Raise(20)),
+            IllegalAction::RaiseWithoutBet
+        );
+        assert_eq!(
+            illegal(&mut game, 0, PlayerAction::Call(0)),
+            IllegalAction::NothingToCall
+        );
+    }
+
+    // Always tries to check, counting how often it gets turned down
+    #[derive(Debug)]
+    struct Checker(Rc<RefCell<u32>>);
+
+    impl Strategy for Checker {
+        fn decide(&mut self, _view: &GameView) -> PlayerAction {
+            PlayerAction::Check
+        }
+
+        fn rejected(&mut self, _action: PlayerAction, error: &IllegalAction) {
+            assert!(matches!(*error, IllegalAction::CheckFacingBet(_)));
+            *self.0.borrow_mut() += 1;
+        }
+    }
+
+    #[test]
+    fn it_folds_for_strategies_that_keep_acting_illegally() {
+        let rejections = Rc::new(RefCell::new(0));
+        let mut players = get_n_dummy_players(2);
+        players.push(init_player_with_strategy(
+            2,
+            "Checker",
+            100,
+            Box::new(Checker(rejections.clone())),
+        ));
+        let mut game = init_seeded_game_state(players, init_blinds(5, 10, None), 8);
+        game.play();
+
+        assert!(*rejections.borrow() > 0);
+        assert_eq!(*rejections.borrow() % MAX_ATTEMPTS, 0);
+        assert_eq!(game.players.iter().map(|p| p.chips).sum::<u32>(), 300);
+    }
+
+    #[test]
+    fn it_steps_through_a_game_one_decision_at_a_time() {
+        let mut game = init_seeded_game_state(get_n_dummy_players(3), init_blinds(5, 10, None), 11);
+        assert_eq!(
+            game.submit(PlayerAction::Fold),
+            Err(IllegalAction::NoDecisionPending)
+        );
+
+        let request = game.next_decision().unwrap();
+        assert_eq!(game.hand_count, 1);
+        assert_eq!(request.seat, 1);
+        assert_eq!(request.view.player_id, 1);
+        assert_eq!(request.view.to_call, 10);
+
+        // Nothing moves until a legal action comes in
+        assert_eq!(game.next_decision().unwrap().seat, 1);
+        assert!(game.submit(PlayerAction::Check).is_err());
+        assert_eq!(game.next_decision().unwrap().seat, 1);
+
+        assert_eq!(
+            game.submit(PlayerAction::Call(10)),
+            Ok(PlayerAction::Call(10))
+        );
+        assert_eq!(game.players[1].chips, 90);
+        assert_eq!(game.next_decision().unwrap().seat, 2);
+
+        // Calling down every decision plays the game to the end
+        let mut decisions = 0;
+        while let Some(request) = game.next_decision() {
+            let action = CallingStation.decide(&request.view);
+            game.submit(action).unwrap();
+            decisions += 1;
+        }
+        assert!(decisions > 0);
+        assert_eq!(game.players.len(), 1);
+        assert_eq!(game.players[0].chips, 300);
+        assert!(game.next_decision().is_none());
+    }
+
+    #[test]
+    fn it_steps_through_the_same_game_that_play_runs() {
+        let mut played =
+            init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, None), 21);
+        played.play();
+
+        let mut stepped =
+            init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, None), 21);
+        while let Some(request) = stepped.next_decision() {
+            let action = stepped.players[request.seat].strategy.decide(&request.view);
+            stepped.submit(action).unwrap();
+        }
+
+        let stacks = |game: &GameState| -> Vec<(PlayerId, u32)> {
+            game.players.iter().map(|p| (p.id, p.chips)).collect()
+        };
+        assert_eq!(played.hand_count, stepped.hand_count);
+        assert_eq!(stacks(&played), stacks(&stepped));
+    }
+
+    #[test]
+    fn it_reports_a_hand_to_observers_as_it_happens() {
+        let (sender, receiver) = mpsc::channel();
+        let mut game = init_seeded_game_state(get_n_dummy_players(3), init_blinds(5, 10, None), 11);
+        game.add_observer(Box::new(sender));
+
+        // Seat 1 is first to act; folding twice leaves the big blind the pot
+        game.next_decision().unwrap();
+        game.submit(PlayerAction::Fold).unwrap();
+        game.next_decision().unwrap();
+        game.submit(PlayerAction::Fold).unwrap();
+        game.next_decision().unwrap();
+
+        let events: Vec<GameEvent> = receiver.try_iter().collect();
+        assert_eq!(events[0], GameEvent::GameStarted { seed: 11 });
+        assert_eq!(
+            events[1],
+            GameEvent::HandStarted {
+                hand_number: 1,
+                table_size: 3,
+                button: 1,
+                small_blind: 2,
+                big_blind: 0,
+                blinds: init_blinds(5, 10, None),
+                structure: BettingStructure::NoLimit,
+                seats: vec![
+                    (0, 0, "Dummy".to_string(), 100),
+                    (1, 1, "Dummy".to_string(), 100),
+                    (2, 2, "Dummy".to_string(), 100),
+                ],
+            }
+        );
+        assert_eq!(
+            events[2],
+            GameEvent::SmallBlindPosted {
+                player: 2,
+                amount: 5
+            }
+        );
+        assert_eq!(
+            events[3],
+            GameEvent::BigBlindPosted {
+                player: 0,
+                amount: 10
+            }
+        );
+        let dealt = events[4..7]
+            .iter()
+            .filter(|e| match **e {
+                GameEvent::HoleCardsDealt { ref cards, .. } => cards.len() == 2,
+                _ => false,
+            })
+            .count();
+        assert_eq!(dealt, 3);
+
+        let rest: Vec<GameEvent> = events[7..].to_vec();
+        assert_eq!(
+            rest[..4].to_vec(),
+            vec![
+                GameEvent::ActionTaken {
+                    player: 1,
+                    action: PlayerAction::Fold,
+                    all_in: false,
+                },
+                GameEvent::ActionTaken {
+                    player: 2,
+                    action: PlayerAction::Fold,
+                    all_in: false,
+                },
+                GameEvent::PotAwarded {
+                    pot: 0,
+                    player: 0,
+                    amount: 15,
+                },
+                GameEvent::HandFinished {
+                    hand_number: 1,
+                    stacks: vec![(0, 105), (1, 100), (2, 95)],
+                },
+            ]
+        );
+        match rest[4] {
+            GameEvent::HandStarted { hand_number: 2, .. } => (),
+            ref other => panic!("expected the next hand, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_reports_every_hand_of_a_session() {
+        let (sender, receiver) = mpsc::channel();
+        let mut game =
+            init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, Some(1)), 5);
+        game.add_observer(Box::new(sender));
+        game.play();
+
+        let events: Vec<GameEvent> = receiver.try_iter().collect();
+        let mut hands = 0;
+        let mut eliminated = 0;
+        for event in &events {
+            match *event {
+                GameEvent::HandFinished { ref stacks, .. } => {
+                    hands += 1;
+                    assert_eq!(stacks.iter().map(|&(_, chips)| chips).sum::<u32>(), 400);
+                }
+                GameEvent::PlayerEliminated { .. } => eliminated += 1,
+                _ => (),
+            }
+        }
+        assert_eq!(hands, game.hand_count);
+        assert_eq!(eliminated, 3);
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::GameOver {
+                winner: Some(game.players[0].id),
+            })
+        );
+    }
+
+    #[test]
+    fn it_stops_at_the_hand_limit() {
+        let (sender, receiver) = mpsc::channel();
+        let mut game = init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, None), 5);
+        game.hand_limit = Some(3);
+        game.add_observer(Box::new(sender));
+        game.play();
+
+        assert_eq!(game.hand_count, 3);
+        assert!(game.players.len() > 1);
+        assert_eq!(
+            receiver.try_iter().last(),
+            Some(GameEvent::GameOver { winner: None })
+        );
+    }
+
+    #[test]
+    fn it_plays_one_hand_at_a_time() {
+        let mut whole = init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, None), 9);
+        let mut by_hand =
+            init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, None), 9);
+        whole.play();
+
+        assert!(by_hand.play_hand());
+        assert_eq!(by_hand.hand_count, 1);
+        assert!(!by_hand.hand_in_progress);
+        assert_eq!(by_hand.awaiting, None);
+        while by_hand.play_hand() {}
+
+        assert_eq!(by_hand.hand_count, whole.hand_count);
+        assert_eq!(by_hand.stacks(), whole.stacks());
+        assert!(!by_hand.play_hand());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_continues_the_same_after_a_serde_round_trip() {
+        let mut game = init_seeded_game_state(get_n_dummy_players(4), init_blinds(5, 10, None), 5);
+        game.hand_limit = Some(3);
+        game.play();
+
+        let json = ::serde_json::to_string(&game).unwrap();
+        let mut loaded: GameState = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.hand_count, 3);
+        assert_eq!(loaded.button, game.button);
+        assert_eq!(loaded.stacks(), game.stacks());
+
+        game.hand_limit = Some(8);
+        loaded.hand_limit = Some(8);
+        game.play();
+        loaded.play();
+
+        assert_eq!(loaded.hand_count, game.hand_count);
+        assert_eq!(loaded.stacks(), game.stacks());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_refuses_to_serialize_a_hand_in_progress() {
+        let mut game = init_seeded_game_state(get_n_dummy_players(3), init_blinds(5, 10, None), 5);
+        game.next_decision();
+
+        assert!(game.hand_in_progress);
+        assert!(::serde_json::to_string(&game).is_err());
+    }
+
+    #[test]
+    fn it_posts_heads_up_blinds_from_the_button() {
+        let mut game = init_seeded_game_state(get_n_dummy_players(2), init_blinds(5, 10, None), 5);
+        game.init_round();
+        game.rotate_button();
+
+        assert_eq!(game.button, 1);
+        assert_eq!(game.small_blind, 1);
+        assert_eq!(game.big_blind, 0);
+
+        // Button acts first preflop, big blind first postflop
+        game.advance_player_to_act();
+        assert_eq!(game.player_to_act, 1);
+        game.transition_street();
+        game.advance_player_to_act();
+        assert_eq!(game.player_to_act, 0);
+    }
+
+    #[test]
+    fn it_moves_to_heads_up_when_a_player_busts() {
+        let mut game = init_seeded_game_state(get_n_dummy_players(3), init_blinds(5, 10, None), 5);
+        game.init_round();
+        game.rotate_button();
+        // Button on player 1, small blind player 2 busts
+        game.players[2].chips = 0;
+        game.end_round();
+        game.init_round();
+        game.rotate_button();
+
+        assert_eq!(game.players.len(), 2);
+        assert_eq!(game.players[game.button].id, 0);
+        assert_eq!(game.small_blind, game.button);
+        assert_eq!(game.players[game.big_blind].id, 1);
+
+        let mut game = init_seeded_game_state(get_n_dummy_players(3), init_blinds(5, 10, None), 5);
+        game.init_round();
+        game.rotate_button();
+        // The button itself busts. Player 0 was just the big blind, so it moves on to player 2
+        // and player 0 takes the button and small blind.
+        let big_blind = game.players[game.big_blind].id;
+        assert_eq!(big_blind, 0);
+        game.players[1].chips = 0;
+        game.end_round();
+        game.init_round();
+        game.rotate_button();
+
+        assert_eq!(game.players[game.button].id, 0);
+        assert_eq!(game.players[game.small_blind].id, 0);
+        assert_eq!(game.players[game.big_blind].id, 2);
+        assert_ne!(game.players[game.big_blind].id, big_blind);
+    }
+
+    #[test]
+    fn it_plays_out_heads_up_sessions() {
+        for seed in 0..20 {
+            let mut game =
+                init_seeded_game_state(get_n_dummy_players(3), init_blinds(5, 10, None), seed);
+            game.play();
+
+            assert_eq!(game.players.len(), 1);
+            assert_eq!(game.players[0].chips, 300);
+        }
+    }
+
+    #[test]
+    fn it_builds_a_single_pot_without_all_ins() {
+        let game =
+            game_with_commitments(&[(20, true, false), (20, true, false), (10, false, false)]);
+
+        assert_eq!(game.pot.chips, 50);
+        assert_eq!(game.pot.participants, ids(&[0, 1]));
+        assert_eq!(game.sidepots.len(), 0);
+    }
+
+    #[test]
+    fn it_layers_side_pots_by_all_in_amounts() {
+        let game = game_with_commitments(&[
+            (10, true, true),
+            (30, true, true),
+            (50, true, false),
+            (50, true, false),
+        ]);
+
+        assert_eq!(game.pot.chips, 40);
+        assert_eq!(game.pot.participants, ids(&[0, 1, 2, 3]));
+        assert_eq!(game.sidepots.len(), 2);
+        assert_eq!(game.sidepots[0].chips, 60);
+        assert_eq!(game.sidepots[0].participants, ids(&[1, 2, 3]));
+        assert_eq!(game.sidepots[1].chips, 40);
+        assert_eq!(game.sidepots[1].participants, ids(&[2, 3]));
+    }
+
+    #[test]
+    fn it_keeps_folded_chips_without_making_them_eligible() {
+        let game =
+            game_with_commitments(&[(10, true, true), (40, false, false), (60, true, false)]);
+
+        assert_eq!(game.pot.chips, 30);
+        assert_eq!(game.pot.participants, ids(&[0, 2]));
+        assert_eq!(game.sidepots.len(), 1);
+        assert_eq!(game.sidepots[0].chips, 80);
+        assert_eq!(game.sidepots[0].participants, ids(&[2]));
+    }
+
+    #[test]
+    fn it_keeps_chips_when_a_folder_out_committed_everyone() {
+        let game =
+            game_with_commitments(&[(5, true, false), (30, false, false), (15, true, false)]);
+
+        assert_eq!(game.pot.chips, 50);
+        assert_eq!(game.pot.participants, ids(&[0, 2]));
+        assert_eq!(game.sidepots.len(), 0);
+    }
+
+    #[test]
+    fn it_awards_side_pots_only_to_eligible_players() {
+        let mut game =
+            game_with_commitments(&[(10, true, true), (50, true, false), (50, true, false)]);
+        for player in &mut game.players {
+            player.chips = 0;
+        }
+        game.board = vec![
+            card_from_str("2", "S"),
+            card_from_str("7", "H"),
+            card_from_str("9", "D"),
+            card_from_str("J", "C"),
+            card_from_str("4", "S"),
+        ];
+        game.players[0].hole_cards = Some(vec![card_from_str("A", "S"), card_from_str("A", "H")]);
+        game.players[1].hole_cards = Some(vec![card_from_str("K", "S"), card_from_str("K", "H")]);
+        game.players[2].hole_cards = Some(vec![card_from_str("Q", "S"), card_from_str("3", "H")]);
+        game.street = Street::Showdown;
+
+        game.award_pots();
+
+        assert_eq!(game.players[0].chips, 30);
+        assert_eq!(game.players[1].chips, 80);
+        assert_eq!(game.players[2].chips, 0);
+    }
+
+    #[test]
+    fn it_returns_uncalled_chips_to_the_bettor() {
+        let mut game = game_with_commitments(&[(20, true, true), (70, true, false)]);
+        for player in &mut game.players {
+            player.chips = 0;
+        }
+        game.board = vec![
+            card_from_str("2", "S"),
+            card_from_str("7", "H"),
+            card_from_str("9", "D"),
+            card_from_str("J", "C"),
+            card_from_str("4", "S"),
+        ];
+        game.players[0].hole_cards = Some(vec![card_from_str("A", "S"), card_from_str("A", "H")]);
+        game.players[1].hole_cards = Some(vec![card_from_str("K", "S"), card_from_str("K", "H")]);
+        game.street = Street::Showdown;
+
+        game.award_pots();
+
+        assert_eq!(game.players[0].chips, 40);
+        assert_eq!(game.players[1].chips, 50);
+    }
 }
@@ -1,9 +1,11 @@
 use std::cmp::Ordering;
 use hand_rankings::*;
 use card::{CardVec, RankVec};
+use card_set::CardSet;
 use itertools::Itertools;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Hand {
     pub cards: CardVec,
     pub hand_rank: HandRank,
@@ -27,6 +29,12 @@ impl PartialEq for Hand {
     }
 }
 
+impl Hand {
+    pub fn card_set(&self) -> CardSet {
+        CardSet::from(&self.cards)
+    }
+}
+
 pub fn init_hand(cards: CardVec) -> Hand {
     let hand_rank = rank_hand(&cards);
     let kickers = get_kickers(&cards, hand_rank);
@@ -46,7 +54,7 @@ pub fn find_best_hand(cards: CardVec) -> Option<Hand> {
             .iter()
             .cloned()
             .combinations(5)
-            .map(|cv| init_hand(cv))
+            .map(init_hand)
             .collect();
         hand_vec.sort_by(|a, b| a.partial_cmp(b).unwrap());
         hand_vec.pop()
@@ -69,6 +77,19 @@ mod tests {
         assert_eq!(hand.kickers, high_card_kickers());
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_round_trips_a_hand_through_json() {
+        let hand = init_hand(two_pair_hand());
+        let json = ::serde_json::to_string(&hand).unwrap();
+        let loaded: Hand = ::serde_json::from_str(&json).unwrap();
+
+        assert!(json.contains("\"cards\":[\""));
+        assert_eq!(loaded.cards, hand.cards);
+        assert_eq!(loaded.hand_rank, HandRank::TwoPair);
+        assert_eq!(loaded.kickers, hand.kickers);
+    }
+
     #[test]
     fn it_finds_identical_hand_equal() {
         let h1 = init_hand(high_card_hand().clone());
@@ -190,6 +211,15 @@ mod tests {
         assert!(eight_high_flush_2 > eight_high_flush);
     }
 
+    #[test]
+    fn it_finds_the_best_hand_in_a_card_set() {
+        let cards = CardSet::from(&straight_flush_hand()) | CardSet::from(&pair_hand());
+        let best_hand = find_best_hand(cards.into()).unwrap();
+
+        assert_eq!(best_hand.hand_rank, HandRank::StraightFlush);
+        assert_eq!(best_hand.card_set(), CardSet::from(&straight_flush_hand()));
+    }
+
     #[test]
     fn it_find_the_best_hand() {
         let board = vec![
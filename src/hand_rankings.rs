@@ -2,6 +2,7 @@ use std::collections::VecDeque;
 use card::*;
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum HandRank {
     HighCard,
     Pair,
@@ -55,9 +56,8 @@ fn is_straight(hand: &CardVec) -> bool {
 
     hand.iter().enumerate().for_each(|(i, c)| {
         //Wheel case
-        if i == 3 && is_straight && c.rank == Rank::Five && hand[i + 1].rank == Rank::Ace {
-            ;
-        } else if is_straight && i < 4 {
+        let wheel = i == 3 && c.rank == Rank::Five && hand[i + 1].rank == Rank::Ace;
+        if is_straight && i < 4 && !wheel {
             is_straight = c.rank.val() + 1 == hand[i + 1].rank.val();
         }
     });
@@ -0,0 +1,981 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::mpsc::Sender;
+
+use card::{cards_to_short_string, CardVec};
+use event::{GameEvent, GameObserver};
+use gamestate::{BettingStructure, Blinds, Pot, Street};
+use hand_rankings::HandRank;
+use player::{init_player, PlayerAction, PlayerId, PlayerVec};
+
+// Everything that happened in one hand, in enough detail to write it out the way poker sites do
+
+// A PokerStars hand id is a single number, so hands played here put the game seed above the hand
+// count. Games appended to the same file then don't repeat each other's ids.
+pub const HAND_ID_SEED_STEP: u64 = 10_000_000_000;
+const HAND_ID_SEEDS: u64 = 1_000_000_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HandHistory {
+    // Sites number hands across all their tables, so these get big
+    pub hand_number: u64,
+    // Only hands played here have a seed. Their hand number is then the game's hand count, and
+    // the two together reseed the hand's shuffle and random choices.
+    pub game_seed: Option<u64>,
+    // Seconds since the unix epoch
+    pub started_at: u64,
+    // Seats at the table, counting ones left empty by busted players
+    pub table_size: usize,
+    // The one player whose hole cards are written out, as a site shows them to whoever is
+    // playing. Everyone else's only appear if they're shown down.
+    pub hero: Option<PlayerId>,
+    pub blinds: Blinds,
+    pub structure: BettingStructure,
+    pub button: PlayerId,
+    pub small_blind: PlayerId,
+    pub big_blind: PlayerId,
+    // In table order, with stacks from before antes and blinds
+    pub seats: Vec<HistorySeat>,
+    pub antes: Vec<(PlayerId, u32)>,
+    // Small blind first
+    pub blinds_posted: Vec<(PlayerId, u32)>,
+    pub hole_cards: Vec<(PlayerId, CardVec)>,
+    pub actions: Vec<RecordedAction>,
+    pub board: CardVec,
+    pub shown: Vec<ShownHand>,
+    pub awards: Vec<PotAward>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistorySeat {
+    pub id: PlayerId,
+    // Index of the seat at the table, written out counting from 1
+    pub seat: usize,
+    pub name: String,
+    pub chips: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecordedAction {
+    pub street: Street,
+    pub player: PlayerId,
+    pub action: PlayerAction,
+    pub all_in: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShownHand {
+    pub player: PlayerId,
+    pub cards: CardVec,
+    pub rank: HandRank,
+}
+
+// Pot 0 is the main pot. Uncalled chips handed back to a bettor come through as an award too.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PotAward {
+    pub pot: usize,
+    pub player: PlayerId,
+    pub amount: u32,
+}
+
+// Puts HandHistories together from the event stream, one per HandFinished. The engine never
+// reads the clock, so every hand is stamped with the start time the caller gives, keeping
+// histories from the same seed identical.
+#[derive(Debug, Default)]
+struct HistoryBuilder {
+    game_seed: Option<u64>,
+    started_at: u64,
+    hero: Option<PlayerId>,
+    street: Option<Street>,
+    current: Option<HandHistory>,
+}
+
+impl HistoryBuilder {
+    fn on_event(&mut self, event: &GameEvent) -> Option<HandHistory> {
+        if let GameEvent::GameStarted { seed } = *event {
+            self.game_seed = Some(seed);
+        }
+        if let GameEvent::HandStarted {
+            hand_number,
+            table_size,
+            button,
+            small_blind,
+            big_blind,
+            blinds,
+            structure,
+            ref seats,
+        } = *event
+        {
+            self.street = Some(Street::PreFlop);
+            self.current = Some(HandHistory {
+                hand_number: u64::from(hand_number),
+                game_seed: self.game_seed,
+                started_at: self.started_at,
+                table_size,
+                hero: self.hero,
+                blinds,
+                structure,
+                button,
+                small_blind,
+                big_blind,
+                seats: seats
+                    .iter()
+                    .map(|&(id, seat, ref name, chips)| HistorySeat {
+                        id,
+                        seat,
+                        name: name.clone(),
+                        chips,
+                    })
+                    .collect(),
+                antes: vec![],
+                blinds_posted: vec![],
+                hole_cards: vec![],
+                actions: vec![],
+                board: vec![],
+                shown: vec![],
+                awards: vec![],
+            });
+            return None;
+        }
+
+        let history = self.current.as_mut()?;
+        match *event {
+            GameEvent::AntePosted { player, amount } => history.antes.push((player, amount)),
+            GameEvent::SmallBlindPosted { player, amount }
+            | GameEvent::BigBlindPosted { player, amount } => {
+                history.blinds_posted.push((player, amount))
+            }
+            GameEvent::HoleCardsDealt { player, ref cards } => {
+                history.hole_cards.push((player, cards.clone()))
+            }
+            GameEvent::ActionTaken {
+                player,
+                action,
+                all_in,
+            } => history.actions.push(RecordedAction {
+                street: self.street.unwrap_or(Street::PreFlop),
+                player,
+                action,
+                all_in,
+            }),
+            GameEvent::StreetDealt {
+                street, ref cards, ..
+            } => {
+                self.street = Some(street);
+                history.board.extend(cards.iter().cloned());
+            }
+            GameEvent::HandShown {
+                player,
+                ref cards,
+                rank,
+            } => history.shown.push(ShownHand {
+                player,
+                cards: cards.clone(),
+                rank,
+            }),
+            GameEvent::PotAwarded {
+                pot,
+                player,
+                amount,
+            } => history.awards.push(PotAward {
+                pot,
+                player,
+                amount,
+            }),
+            GameEvent::HandFinished { .. } => return self.current.take(),
+            _ => (),
+        }
+        None
+    }
+}
+
+fn init_history_builder(started_at: u64, hero: Option<PlayerId>) -> HistoryBuilder {
+    HistoryBuilder {
+        started_at,
+        hero,
+        ..HistoryBuilder::default()
+    }
+}
+
+// Sends each finished hand down a channel
+#[derive(Debug)]
+pub struct HistoryRecorder {
+    builder: HistoryBuilder,
+    sender: Sender<HandHistory>,
+}
+
+impl GameObserver for HistoryRecorder {
+    fn on_event(&mut self, event: &GameEvent) {
+        if let Some(history) = self.builder.on_event(event) {
+            let _ = self.sender.send(history);
+        }
+    }
+}
+
+// started_at is in seconds since the unix epoch
+pub fn init_history_recorder(sender: Sender<HandHistory>, started_at: u64) -> HistoryRecorder {
+    HistoryRecorder {
+        builder: init_history_builder(started_at, None),
+        sender,
+    }
+}
+
+// Writes each finished hand out in PokerStars format
+pub struct HistoryWriter<W> {
+    builder: HistoryBuilder,
+    output: W,
+    // The first write that failed. Nothing more is written once this is set.
+    error: Option<io::Error>,
+}
+
+impl<W> HistoryWriter<W> {
+    // Hands the failed write back, so whoever runs the game can stop and report it
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+impl<W> fmt::Debug for HistoryWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HistoryWriter")
+    }
+}
+
+impl<W: Write> GameObserver for HistoryWriter<W> {
+    fn on_event(&mut self, event: &GameEvent) {
+        if let Some(history) = self.builder.on_event(event) {
+            if self.error.is_none() {
+                self.error = history.write_pokerstars(&mut self.output).err();
+            }
+        }
+    }
+}
+
+// Only the hero's hole cards are written, the way a site writes them for whoever is playing
+pub fn init_history_writer<W: Write>(
+    output: W,
+    started_at: u64,
+    hero: Option<PlayerId>,
+) -> HistoryWriter<W> {
+    HistoryWriter {
+        builder: init_history_builder(started_at, hero),
+        output,
+        error: None,
+    }
+}
+
+fn street_name(street: Street) -> &'static str {
+    match street {
+        Street::PreFlop => "Pre-Flop",
+        Street::Flop => "Flop",
+        Street::Turn => "Turn",
+        Street::River => "River",
+        Street::Showdown => "Showdown",
+    }
+}
+
+fn structure_name(structure: BettingStructure) -> &'static str {
+    match structure {
+        BettingStructure::NoLimit => "No Limit",
+        BettingStructure::PotLimit => "Pot Limit",
+        BettingStructure::FixedLimit { .. } => "Limit",
+    }
+}
+
+pub fn describe_rank(rank: HandRank) -> &'static str {
+    match rank {
+        HandRank::HighCard => "high card",
+        HandRank::Pair => "a pair",
+        HandRank::TwoPair => "two pair",
+        HandRank::ThreeOfAKind => "three of a kind",
+        HandRank::Straight => "a straight",
+        HandRank::Flush => "a flush",
+        HandRank::FullHouse => "a full house",
+        HandRank::FourOfAKind => "four of a kind",
+        HandRank::StraightFlush => "a straight flush",
+    }
+}
+
+// "YYYY/MM/DD HH:MM:SS", using the days-to-civil-date conversion from
+// http://howardhinnant.github.io/date_algorithms.html
+pub fn format_timestamp(secs: u64) -> String {
+    let days = secs / 86_400;
+    let time = secs % 86_400;
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{}/{:02}/{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+// Inverse of format_timestamp
+pub fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let mut parts = timestamp.split_whitespace();
+    let date: Vec<u64> = parts
+        .next()?
+        .split('/')
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    let time: Vec<u64> = parts
+        .next()?
+        .split(':')
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    if date.len() != 3
+        || time.len() != 3
+        || date[0] < 1970
+        || !(1..=12).contains(&date[1])
+        || !(1..=31).contains(&date[2])
+        || time[0] > 23
+        || time[1] > 59
+        || time[2] > 59
+    {
+        return None;
+    }
+    let (month, day) = (date[1], date[2]);
+    let year = if month <= 2 { date[0] - 1 } else { date[0] };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    // Only the year is unbounded, so a far enough future date is all that can overflow
+    let days = era.checked_mul(146_097)?.checked_add(doe)? - 719_468;
+    days.checked_mul(86_400)?
+        .checked_add(time[0] * 3600 + time[1] * 60 + time[2])
+}
+
+impl HandHistory {
+    pub fn name(&self, id: PlayerId) -> String {
+        self.seats
+            .iter()
+            .find(|s| s.id == id)
+            .map_or_else(|| format!("Player {}", id), |s| s.name.clone())
+    }
+
+    // The number written after "Hand #"
+    pub fn hand_id(&self) -> u64 {
+        match self.game_seed {
+            Some(seed) => seed % HAND_ID_SEEDS * HAND_ID_SEED_STEP + self.hand_number,
+            None => self.hand_number,
+        }
+    }
+
+    fn seat_number(&self, id: PlayerId) -> usize {
+        self.seats
+            .iter()
+            .find(|s| s.id == id)
+            .map_or(0, |s| s.seat + 1)
+    }
+
+    // Chips each player bet, leaving out antes
+    fn bets(&self) -> HashMap<PlayerId, u32> {
+        let mut street_committed: HashMap<PlayerId, u32> = HashMap::new();
+        let mut bets: HashMap<PlayerId, u32> = HashMap::new();
+        let mut street = Street::PreFlop;
+        for &(player, amount) in &self.blinds_posted {
+            *street_committed.entry(player).or_insert(0) += amount;
+        }
+        for action in &self.actions {
+            if action.street != street {
+                for (&player, &chips) in &street_committed {
+                    *bets.entry(player).or_insert(0) += chips;
+                }
+                street_committed.clear();
+                street = action.street;
+            }
+            let committed = street_committed.entry(action.player).or_insert(0);
+            match action.action {
+                PlayerAction::Bet(to) | PlayerAction::Raise(to) => *committed = to,
+                PlayerAction::Call(n) => *committed += n,
+                PlayerAction::Check | PlayerAction::Fold => (),
+            }
+        }
+        for (&player, &chips) in &street_committed {
+            *bets.entry(player).or_insert(0) += chips;
+        }
+        bets
+    }
+
+    // The table as it was dealt, with anyone who folded out of the hand
+    pub fn players(&self) -> PlayerVec {
+        self.seats
+            .iter()
+            .map(|seat| {
+                let mut player = init_player(seat.id, &seat.name, seat.chips);
+                player.seat = seat.seat;
+                player.hole_cards = self
+                    .hole_cards
+                    .iter()
+                    .find(|&&(id, _)| id == seat.id)
+                    .map(|(_, cards)| cards.clone());
+                player.in_hand = !self.folded(seat.id);
+                player
+            })
+            .collect()
+    }
+
+    fn folded(&self, id: PlayerId) -> bool {
+        self.actions
+            .iter()
+            .any(|a| a.player == id && a.action == PlayerAction::Fold)
+    }
+
+    // The pots as they were collected. Each one is open to everyone still in the hand who put in
+    // at least as much as the pot's all-in level.
+    pub fn pots(&self) -> Vec<Pot> {
+        let mut committed = self.bets();
+        for &(player, ante) in &self.antes {
+            *committed.entry(player).or_insert(0) += ante;
+        }
+        if let Some((player, uncalled)) = self.uncalled_bet() {
+            if let Some(chips) = committed.get_mut(&player) {
+                *chips -= uncalled;
+            }
+        }
+        let mut levels: Vec<u32> = committed
+            .iter()
+            .filter(|&(&id, _)| !self.folded(id))
+            .map(|(_, &chips)| chips)
+            .collect();
+        levels.sort();
+        levels.dedup();
+
+        let collected = self.collected();
+        let num_pots = collected.iter().map(|a| a.pot + 1).max().unwrap_or(0);
+        (0..num_pots)
+            .map(|pot| {
+                let level = levels
+                    .get(pot)
+                    .or_else(|| levels.last())
+                    .cloned()
+                    .unwrap_or(0);
+                Pot {
+                    chips: collected
+                        .iter()
+                        .filter(|a| a.pot == pot)
+                        .map(|a| a.amount)
+                        .sum(),
+                    participants: committed
+                        .iter()
+                        .filter(|&(&id, &chips)| !self.folded(id) && chips >= level)
+                        .map(|(&id, _)| id)
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    // The part of the biggest bet nobody matched, which the engine hands back as a pot of its own
+    pub fn uncalled_bet(&self) -> Option<(PlayerId, u32)> {
+        let bets = self.bets();
+        let (&top_player, &top) = bets.iter().max_by_key(|&(_, &chips)| chips)?;
+        let next = bets
+            .iter()
+            .filter(|&(&player, _)| player != top_player)
+            .map(|(_, &chips)| chips)
+            .max()
+            .unwrap_or(0);
+        let awarded: u32 = self
+            .awards
+            .iter()
+            .filter(|a| a.player == top_player)
+            .map(|a| a.amount)
+            .sum();
+        let uncalled = (top - next).min(awarded);
+        if uncalled > 0 {
+            Some((top_player, uncalled))
+        } else {
+            None
+        }
+    }
+
+    // Awards with any uncalled bet taken out, dropping pots left empty, renumbered from 0
+    pub fn collected(&self) -> Vec<PotAward> {
+        let mut awards = self.awards.clone();
+        if let Some((player, mut uncalled)) = self.uncalled_bet() {
+            for award in awards.iter_mut().rev().filter(|a| a.player == player) {
+                let taken = uncalled.min(award.amount);
+                award.amount -= taken;
+                uncalled -= taken;
+            }
+        }
+        awards.retain(|a| a.amount > 0);
+
+        let mut pots: Vec<usize> = awards.iter().map(|a| a.pot).collect();
+        pots.sort();
+        pots.dedup();
+        for award in &mut awards {
+            award.pot = pots.iter().position(|&p| p == award.pot).unwrap_or(0);
+        }
+        awards
+    }
+
+    pub fn to_pokerstars(&self) -> String {
+        let mut output = vec![];
+        // Writing to a Vec can't fail
+        let _ = self.write_pokerstars(&mut output);
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    // Play money cash game format
+    pub fn write_pokerstars<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "PokerStars Hand #{}: Hold'em {} ({}/{}) - {} UTC",
+            self.hand_id(),
+            structure_name(self.structure),
+            self.blinds.sb,
+            self.blinds.bb,
+            format_timestamp(self.started_at)
+        )?;
+        let table = match self.game_seed {
+            Some(seed) => format!("Pokers {}", seed),
+            None => "Pokers".to_string(),
+        };
+        writeln!(
+            out,
+            "Table '{}' {}-max Seat #{} is the button",
+            table,
+            self.table_size,
+            self.seat_number(self.button)
+        )?;
+        for seat in &self.seats {
+            writeln!(
+                out,
+                "Seat {}: {} ({} in chips)",
+                seat.seat + 1,
+                seat.name,
+                seat.chips
+            )?;
+        }
+
+        let mut stacks: HashMap<PlayerId, u32> =
+            self.seats.iter().map(|s| (s.id, s.chips)).collect();
+        let mut pay = |player: PlayerId, amount: u32| -> &'static str {
+            let stack = stacks.entry(player).or_insert(0);
+            *stack = stack.saturating_sub(amount);
+            if *stack == 0 {
+                " and is all-in"
+            } else {
+                ""
+            }
+        };
+        for &(player, amount) in &self.antes {
+            let all_in = pay(player, amount);
+            writeln!(
+                out,
+                "{}: posts the ante {}{}",
+                self.name(player),
+                amount,
+                all_in
+            )?;
+        }
+        let mut street_committed: HashMap<PlayerId, u32> = HashMap::new();
+        for &(player, amount) in &self.blinds_posted {
+            let blind = if player == self.big_blind {
+                "big"
+            } else {
+                "small"
+            };
+            let all_in = pay(player, amount);
+            *street_committed.entry(player).or_insert(0) += amount;
+            writeln!(
+                out,
+                "{}: posts {} blind {}{}",
+                self.name(player),
+                blind,
+                amount,
+                all_in
+            )?;
+        }
+
+        writeln!(out, "*** HOLE CARDS ***")?;
+        let hero_cards = self
+            .hole_cards
+            .iter()
+            .find(|&&(id, _)| Some(id) == self.hero);
+        if let Some(&(player, ref cards)) = hero_cards {
+            writeln!(
+                out,
+                "Dealt to {} [{}]",
+                self.name(player),
+                cards_to_short_string(cards)
+            )?;
+        }
+
+        let uncalled = self.uncalled_bet();
+        // When nobody gets to act, the big blind's uncalled part goes back after the posts
+        let last_street = self.actions.last().map_or(Street::PreFlop, |a| a.street);
+        let mut current_bet = self.blinds.bb;
+        for &(street, dealt) in &[
+            (Street::PreFlop, 0),
+            (Street::Flop, 3),
+            (Street::Turn, 4),
+            (Street::River, 5),
+        ] {
+            if street != Street::PreFlop {
+                if self.board.len() < dealt {
+                    break;
+                }
+                match street {
+                    Street::Flop => {
+                        writeln!(
+                            out,
+                            "*** FLOP *** [{}]",
+                            cards_to_short_string(&self.board[..3])
+                        )
+                    }
+                    _ => writeln!(
+                        out,
+                        "*** {} *** [{}] [{}]",
+                        street_name(street).to_uppercase(),
+                        cards_to_short_string(&self.board[..dealt - 1]),
+                        self.board[dealt - 1].to_short_string()
+                    ),
+                }?;
+                street_committed.clear();
+                current_bet = 0;
+            }
+
+            for action in self.actions.iter().filter(|a| a.street == street) {
+                let committed = street_committed.entry(action.player).or_insert(0);
+                let line = match action.action {
+                    PlayerAction::Fold => "folds".to_string(),
+                    PlayerAction::Check => "checks".to_string(),
+                    PlayerAction::Call(n) => {
+                        *committed += n;
+                        format!("calls {}", n)
+                    }
+                    PlayerAction::Bet(to) => {
+                        let bet = to - *committed;
+                        *committed = to;
+                        current_bet = current_bet.max(to);
+                        format!("bets {}", bet)
+                    }
+                    PlayerAction::Raise(to) => {
+                        let by = to.saturating_sub(current_bet);
+                        *committed = to;
+                        current_bet = current_bet.max(to);
+                        format!("raises {} to {}", by, to)
+                    }
+                };
+                let all_in = if action.all_in { " and is all-in" } else { "" };
+                writeln!(out, "{}: {}{}", self.name(action.player), line, all_in)?;
+            }
+            if let Some((player, amount)) = uncalled {
+                if last_street == street {
+                    writeln!(
+                        out,
+                        "Uncalled bet ({}) returned to {}",
+                        amount,
+                        self.name(player)
+                    )?;
+                }
+            }
+        }
+
+        if !self.shown.is_empty() {
+            writeln!(out, "*** SHOW DOWN ***")?;
+            for shown in &self.shown {
+                writeln!(
+                    out,
+                    "{}: shows [{}] ({})",
+                    self.name(shown.player),
+                    cards_to_short_string(&shown.cards),
+                    describe_rank(shown.rank)
+                )?;
+            }
+        }
+        let collected = self.collected();
+        let num_pots = collected.iter().map(|a| a.pot + 1).max().unwrap_or(0);
+        let pot_name = |pot: usize| match (num_pots, pot) {
+            (1, _) => "pot".to_string(),
+            (_, 0) => "main pot".to_string(),
+            (2, _) => "side pot".to_string(),
+            _ => format!("side pot-{}", pot),
+        };
+        // Side pots are settled before the main pot
+        for pot in (0..num_pots).rev() {
+            for award in collected.iter().filter(|a| a.pot == pot) {
+                writeln!(
+                    out,
+                    "{} collected {} from {}",
+                    self.name(award.player),
+                    award.amount,
+                    pot_name(award.pot)
+                )?;
+            }
+        }
+
+        writeln!(out, "*** SUMMARY ***")?;
+        let pot_total = |pot: usize| -> u32 {
+            collected
+                .iter()
+                .filter(|a| a.pot == pot)
+                .map(|a| a.amount)
+                .sum()
+        };
+        write!(
+            out,
+            "Total pot {}",
+            collected.iter().map(|a| a.amount).sum::<u32>()
+        )?;
+        if num_pots > 1 {
+            write!(out, " Main pot {}.", pot_total(0))?;
+            for pot in 1..num_pots {
+                if num_pots == 2 {
+                    write!(out, " Side pot {}.", pot_total(pot))?;
+                } else {
+                    write!(out, " Side pot-{} {}.", pot, pot_total(pot))?;
+                }
+            }
+        }
+        writeln!(out, " | Rake 0")?;
+        if !self.board.is_empty() {
+            writeln!(out, "Board [{}]", cards_to_short_string(&self.board))?;
+        }
+
+        for seat in &self.seats {
+            write!(out, "Seat {}: {}", seat.seat + 1, seat.name)?;
+            if seat.id == self.button {
+                write!(out, " (button)")?;
+            }
+            if seat.id == self.small_blind {
+                write!(out, " (small blind)")?;
+            } else if seat.id == self.big_blind {
+                write!(out, " (big blind)")?;
+            }
+
+            let won: u32 = collected
+                .iter()
+                .filter(|a| a.player == seat.id)
+                .map(|a| a.amount)
+                .sum();
+            let fold = self
+                .actions
+                .iter()
+                .find(|a| a.player == seat.id && a.action == PlayerAction::Fold);
+            let shown = self.shown.iter().find(|s| s.player == seat.id);
+            if let Some(fold) = fold {
+                if fold.street == Street::PreFlop {
+                    let put_in = self.actions.iter().any(|a| {
+                        a.player == seat.id
+                            && a.street == Street::PreFlop
+                            && !matches!(a.action, PlayerAction::Check | PlayerAction::Fold)
+                    });
+                    write!(out, " folded before Flop")?;
+                    if !put_in {
+                        write!(out, " (didn't bet)")?;
+                    }
+                } else {
+                    write!(out, " folded on the {}", street_name(fold.street))?;
+                }
+            } else if let Some(shown) = shown {
+                write!(
+                    out,
+                    " showed [{}] and ",
+                    cards_to_short_string(&shown.cards)
+                )?;
+                if won > 0 {
+                    write!(out, "won ({}) with {}", won, describe_rank(shown.rank))?;
+                } else {
+                    write!(out, "lost with {}", describe_rank(shown.rank))?;
+                }
+            } else if won > 0 {
+                write!(out, " collected ({})", won)?;
+            }
+            writeln!(out)?;
+        }
+        writeln!(out)?;
+        writeln!(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::parse_cards;
+    use gamestate::{init_blinds, init_seeded_game_state};
+    use player::init_players;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::mpsc;
+
+    fn folded_around_history() -> HandHistory {
+        let seat = |id, name: &str| HistorySeat {
+            id,
+            seat: id as usize,
+            name: name.to_string(),
+            chips: 100,
+        };
+        let action = |street, player, action| RecordedAction {
+            street,
+            player,
+            action,
+            all_in: false,
+        };
+        HandHistory {
+            hand_number: 3,
+            game_seed: Some(42),
+            started_at: 1_000_000_000,
+            table_size: 6,
+            hero: Some(2),
+            blinds: init_blinds(5, 10, None),
+            structure: BettingStructure::NoLimit,
+            button: 0,
+            small_blind: 1,
+            big_blind: 2,
+            seats: vec![seat(0, "Alice"), seat(1, "Bob"), seat(2, "Carol")],
+            antes: vec![],
+            blinds_posted: vec![(1, 5), (2, 10)],
+            hole_cards: vec![
+                (0, parse_cards("AsKh").unwrap()),
+                (1, parse_cards("7c2d").unwrap()),
+                (2, parse_cards("QsQd").unwrap()),
+            ],
+            actions: vec![
+                action(Street::PreFlop, 0, PlayerAction::Raise(30)),
+                action(Street::PreFlop, 1, PlayerAction::Fold),
+                action(Street::PreFlop, 2, PlayerAction::Call(20)),
+                action(Street::Flop, 2, PlayerAction::Check),
+                action(Street::Flop, 0, PlayerAction::Bet(40)),
+                action(Street::Flop, 2, PlayerAction::Fold),
+            ],
+            board: parse_cards("7h8h2c").unwrap(),
+            shown: vec![],
+            awards: vec![PotAward {
+                pot: 0,
+                player: 0,
+                amount: 105,
+            }],
+        }
+    }
+
+    #[test]
+    fn it_formats_timestamps() {
+        assert_eq!(format_timestamp(0), "1970/01/01 00:00:00");
+        assert_eq!(format_timestamp(1_000_000_000), "2001/09/09 01:46:40");
+        assert_eq!(format_timestamp(1_709_210_096), "2024/02/29 12:34:56");
+        for &secs in &[0, 951_782_400, 1_000_000_000, 1_709_210_096, 4_102_444_799] {
+            assert_eq!(parse_timestamp(&format_timestamp(secs)), Some(secs));
+        }
+        assert_eq!(parse_timestamp("2024/13/01 00:00:00"), None);
+        assert_eq!(parse_timestamp("2020/03/00 00:00:00"), None);
+        assert_eq!(parse_timestamp("2020/03/01 24:00:00"), None);
+        assert_eq!(parse_timestamp("99999999999999999/03/01 00:00:00"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn it_writes_pokerstars_histories() {
+        let history = folded_around_history();
+
+        assert_eq!(history.uncalled_bet(), Some((0, 40)));
+        assert_eq!(
+            history.to_pokerstars(),
+            "PokerStars Hand #420000000003: Hold'em No Limit (5/10) - 2001/09/09 01:46:40 UTC
+Table 'Pokers 42' 6-max Seat #1 is the button
+Seat 1: Alice (100 in chips)
+Seat 2: Bob (100 in chips)
+Seat 3: Carol (100 in chips)
+Bob: posts small blind 5
+Carol: posts big blind 10
+*** HOLE CARDS ***
+Dealt to Carol [Qs Qd]
+Alice: raises 20 to 30
+Bob: folds
+Carol: calls 20
+*** FLOP *** [7h 8h 2c]
+Carol: checks
+Alice: bets 40
+Carol: folds
+Uncalled bet (40) returned to Alice
+Alice collected 65 from pot
+*** SUMMARY ***
+Total pot 65 | Rake 0
+Board [7h 8h 2c]
+Seat 1: Alice (button) collected (65)
+Seat 2: Bob (small blind) folded before Flop (didn't bet)
+Seat 3: Carol (big blind) folded on the Flop
+
+
+"
+        );
+    }
+
+    #[derive(Debug)]
+    struct FullDisk;
+
+    impl Write for FullDisk {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_first_write_error() {
+        let writer = Rc::new(RefCell::new(init_history_writer(FullDisk, 0, None)));
+        let mut game = init_seeded_game_state(init_players(3, 0, 100), init_blinds(1, 2, None), 8);
+        game.add_observer(Box::new(writer.clone()));
+        game.play_hand();
+        game.play_hand();
+
+        let mut writer = writer.borrow_mut();
+        assert_eq!(writer.take_error().unwrap().to_string(), "disk full");
+        assert!(writer.take_error().is_none());
+    }
+
+    #[test]
+    fn it_records_every_hand_of_a_game() {
+        let (sender, receiver) = mpsc::channel();
+        let mut game =
+            init_seeded_game_state(init_players(4, 0, 100), init_blinds(1, 2, Some(1)), 8);
+        game.add_observer(Box::new(init_history_recorder(sender, 1_000_000_000)));
+        game.play();
+
+        let histories: Vec<HandHistory> = receiver.try_iter().collect();
+        assert_eq!(histories.len() as u32, game.hand_count);
+        assert!(histories.iter().any(|h| !h.shown.is_empty()));
+        for history in &histories {
+            assert_eq!(history.game_seed, Some(8));
+            assert_eq!(history.started_at, 1_000_000_000);
+            // Busted players' seats still count towards the table size, and nobody moves seats
+            assert_eq!(history.table_size, 4);
+            for seat in &history.seats {
+                assert_eq!(seat.seat, seat.id as usize);
+            }
+            assert_eq!(history.hole_cards.len(), history.seats.len());
+            assert_eq!(history.antes.len(), history.seats.len());
+
+            // Every chip put in comes back out as an award
+            let put_in: u32 = history.antes.iter().map(|&(_, chips)| chips).sum::<u32>()
+                + history.bets().values().sum::<u32>();
+            let awarded: u32 = history.awards.iter().map(|a| a.amount).sum();
+            assert_eq!(put_in, awarded);
+
+            let text = history.to_pokerstars();
+            let uncalled = history.uncalled_bet().map_or(0, |(_, chips)| chips);
+            assert!(text.starts_with(&format!("PokerStars Hand #{}:", history.hand_id())));
+            assert!(text.contains(&format!("Total pot {}", awarded - uncalled)));
+        }
+    }
+}
@@ -0,0 +1,758 @@
+use std::collections::HashMap;
+
+use card::{parse_cards, CardVec};
+use evaluator::evaluate;
+use gamestate::{init_blinds, BettingStructure, Street, DEFAULT_RAISE_CAP};
+use history::{
+    parse_timestamp, HandHistory, HistorySeat, PotAward, RecordedAction, ShownHand,
+    HAND_ID_SEED_STEP,
+};
+use player::{PlayerAction, PlayerId};
+
+// Reads PokerStars hold'em hand histories back into HandHistories. Seats become players with ids
+// one less than their seat number, and money games are counted in cents.
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum HistoryParseError {
+    #[fail(display = "Line {}: expected a PokerStars hand header, got {}", _0, _1)]
+    ExpectedHeader(usize, String),
+    #[fail(display = "Line {}: unsupported game {}", _0, _1)]
+    UnsupportedGame(usize, String),
+    #[fail(display = "Line {}: couldn't parse {}", _0, _1)]
+    MalformedLine(usize, String),
+    #[fail(display = "Line {}: nobody named {} is seated", _0, _1)]
+    UnknownPlayer(usize, String),
+    #[fail(display = "Line {}: invalid amount {}", _0, _1)]
+    InvalidAmount(usize, String),
+    #[fail(display = "Line {}: invalid cards {}", _0, _1)]
+    InvalidCards(usize, String),
+    #[fail(display = "Line {}: hand ends before its summary", _0)]
+    Truncated(usize),
+}
+
+impl HistoryParseError {
+    pub fn line(&self) -> usize {
+        match *self {
+            HistoryParseError::ExpectedHeader(line, _)
+            | HistoryParseError::UnsupportedGame(line, _)
+            | HistoryParseError::MalformedLine(line, _)
+            | HistoryParseError::UnknownPlayer(line, _)
+            | HistoryParseError::InvalidAmount(line, _)
+            | HistoryParseError::InvalidCards(line, _)
+            | HistoryParseError::Truncated(line) => line,
+        }
+    }
+}
+
+// Any number of hands, one after another. Lines that don't matter to the hand, like chat or
+// players joining and leaving, are skipped.
+pub fn parse_pokerstars(text: &str) -> Result<Vec<HandHistory>, HistoryParseError> {
+    let mut hands = vec![];
+    let mut parser: Option<HandParser> = None;
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if is_header(line) {
+            if let Some(parser) = parser.take() {
+                hands.push(parser.finish()?);
+            }
+            parser = Some(HandParser::new(number, line)?);
+        } else if let Some(ref mut parser) = parser {
+            parser.parse_line(number, line)?;
+        } else if !line.is_empty() {
+            return Err(HistoryParseError::ExpectedHeader(number, line.to_string()));
+        }
+    }
+    if let Some(parser) = parser {
+        hands.push(parser.finish()?);
+    }
+    Ok(hands)
+}
+
+fn is_header(line: &str) -> bool {
+    line.starts_with("PokerStars ") && (line.contains("Hand #") || line.contains("Game #"))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Section {
+    Seats,
+    Actions,
+    Summary,
+}
+
+#[derive(Debug)]
+struct HandParser {
+    header_line: usize,
+    cents: bool,
+    section: Section,
+    street: Street,
+    street_committed: HashMap<PlayerId, u32>,
+    uncalled: Option<(PlayerId, u32)>,
+    history: HandHistory,
+}
+
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let from = text.find(start)? + start.len();
+    let to = text[from..].find(end)? + from;
+    Some(&text[from..to])
+}
+
+impl HandParser {
+    // e.g. "PokerStars Hand #1: Hold'em No Limit ($0.01/$0.02 USD) - 2020/01/01 12:00:00 ET"
+    fn new(number: usize, line: &str) -> Result<HandParser, HistoryParseError> {
+        let malformed = || HistoryParseError::MalformedLine(number, line.to_string());
+        let hand_number = between(line, "#", ":")
+            .and_then(|n| n.trim().parse().ok())
+            .ok_or_else(malformed)?;
+
+        let game = line[line.find(':').ok_or_else(malformed)? + 1..].trim();
+        let structure = if game.contains("Hold'em No Limit") {
+            BettingStructure::NoLimit
+        } else if game.contains("Hold'em Pot Limit") {
+            BettingStructure::PotLimit
+        } else if game.contains("Hold'em Limit") {
+            BettingStructure::FixedLimit {
+                cap: DEFAULT_RAISE_CAP,
+            }
+        } else {
+            let game = game.split(" - ").next().unwrap_or(game);
+            return Err(HistoryParseError::UnsupportedGame(number, game.to_string()));
+        };
+
+        let after_game = &game[game.find("Hold'em").unwrap_or(0)..];
+        let stakes = between(after_game, "(", ")")
+            .and_then(|s| s.split_whitespace().next())
+            .ok_or_else(malformed)?;
+        let cents = stakes.contains(&['$', '€', '£'][..]);
+        let mut blinds = stakes.split('/');
+        let (sb, bb) = match (blinds.next(), blinds.next()) {
+            (Some(sb), Some(bb)) => (
+                parse_amount(number, sb, cents)?,
+                parse_amount(number, bb, cents)?,
+            ),
+            _ => return Err(malformed()),
+        };
+        let started_at = after_game
+            .find(") - ")
+            .and_then(|i| parse_timestamp(&after_game[i + 4..]))
+            .unwrap_or(0);
+
+        Ok(HandParser {
+            header_line: number,
+            cents,
+            section: Section::Seats,
+            street: Street::PreFlop,
+            street_committed: HashMap::new(),
+            uncalled: None,
+            history: HandHistory {
+                hand_number,
+                game_seed: None,
+                started_at,
+                table_size: 0,
+                hero: None,
+                blinds: init_blinds(sb, bb, None),
+                structure,
+                button: 0,
+                small_blind: 0,
+                big_blind: 0,
+                seats: vec![],
+                antes: vec![],
+                blinds_posted: vec![],
+                hole_cards: vec![],
+                actions: vec![],
+                board: vec![],
+                shown: vec![],
+                awards: vec![],
+            },
+        })
+    }
+
+    fn parse_line(&mut self, number: usize, line: &str) -> Result<(), HistoryParseError> {
+        if line.starts_with("*** ") {
+            return self.parse_section(number, line);
+        }
+        match self.section {
+            Section::Seats if line.starts_with("Table '") => self.parse_table(number, line),
+            Section::Seats if line.starts_with("Seat ") => self.parse_seat(number, line),
+            Section::Seats => match self.player_line(line) {
+                Some((player, rest)) => self.parse_post(number, player, rest),
+                None => Ok(()),
+            },
+            Section::Actions => self.parse_action_line(number, line),
+            Section::Summary => Ok(()),
+        }
+    }
+
+    fn parse_section(&mut self, number: usize, line: &str) -> Result<(), HistoryParseError> {
+        let street = if line.starts_with("*** HOLE CARDS ***") {
+            self.section = Section::Actions;
+            return Ok(());
+        } else if line.starts_with("*** SUMMARY ***") {
+            self.section = Section::Summary;
+            return Ok(());
+        } else if line.starts_with("*** FLOP ***") {
+            Street::Flop
+        } else if line.starts_with("*** TURN ***") {
+            Street::Turn
+        } else if line.starts_with("*** RIVER ***") {
+            Street::River
+        } else if line.starts_with("*** SHOW DOWN ***") {
+            Street::Showdown
+        } else {
+            return Err(HistoryParseError::MalformedLine(number, line.to_string()));
+        };
+
+        if street != Street::Showdown {
+            // The whole board so far is in the brackets, with the new card split off
+            let cards: String = line
+                .split('[')
+                .skip(1)
+                .map(|group| group.split(']').next().unwrap_or(""))
+                .collect::<Vec<&str>>()
+                .join(" ");
+            self.history.board = parse_cards(&cards)
+                .map_err(|_| HistoryParseError::InvalidCards(number, cards.clone()))?;
+        }
+        self.street = street;
+        self.street_committed.clear();
+        Ok(())
+    }
+
+    // e.g. "Table 'Pokers 42' 6-max Seat #3 is the button"
+    fn parse_table(&mut self, number: usize, line: &str) -> Result<(), HistoryParseError> {
+        if let Some(seed) = between(line, "'", "'").and_then(|n| n.strip_prefix("Pokers ")) {
+            self.history.game_seed = seed.parse().ok();
+            if self.history.game_seed.is_some() {
+                self.history.hand_number %= HAND_ID_SEED_STEP;
+            }
+        }
+        if let Some(size) = between(line, "' ", "-max") {
+            self.history.table_size = size.parse().unwrap_or(0);
+        }
+        let button = line
+            .find("Seat #")
+            .map(|i| &line[i + 6..])
+            .and_then(|s| s.split_whitespace().next())
+            .and_then(|s| s.parse::<PlayerId>().ok())
+            .filter(|&seat| seat > 0)
+            .ok_or_else(|| HistoryParseError::MalformedLine(number, line.to_string()))?;
+        self.history.button = button - 1;
+        self.history.small_blind = button - 1;
+        self.history.big_blind = button - 1;
+        Ok(())
+    }
+
+    // e.g. "Seat 1: Alice ($2.50 in chips)", maybe followed by "is sitting out"
+    fn parse_seat(&mut self, number: usize, line: &str) -> Result<(), HistoryParseError> {
+        let malformed = || HistoryParseError::MalformedLine(number, line.to_string());
+        let seat = between(line, "Seat ", ":")
+            .and_then(|s| s.parse::<PlayerId>().ok())
+            .filter(|&seat| seat > 0)
+            .ok_or_else(malformed)?;
+        let rest = line[line.find(':').ok_or_else(malformed)? + 1..].trim();
+        let in_chips = rest.find(" in chips").ok_or_else(malformed)?;
+        let open = rest[..in_chips].rfind(" (").ok_or_else(malformed)?;
+        if rest[in_chips..].contains("sitting out") || rest[in_chips..].contains("out of hand") {
+            return Ok(());
+        }
+        let chips = parse_amount(number, &rest[open + 2..in_chips], self.cents)?;
+        self.history.seats.push(HistorySeat {
+            id: seat - 1,
+            seat: (seat - 1) as usize,
+            name: rest[..open].to_string(),
+            chips,
+        });
+        Ok(())
+    }
+
+    // Splits "Name: rest" on the longest seated name it starts with
+    fn player_line<'a>(&self, line: &'a str) -> Option<(PlayerId, &'a str)> {
+        self.history
+            .seats
+            .iter()
+            .filter(|s| line.starts_with(&s.name) && line[s.name.len()..].starts_with(": "))
+            .max_by_key(|s| s.name.len())
+            .map(|s| (s.id, line[s.name.len() + 2..].trim()))
+    }
+
+    fn player_named(&self, number: usize, name: &str) -> Result<PlayerId, HistoryParseError> {
+        self.history
+            .seats
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.id)
+            .ok_or_else(|| HistoryParseError::UnknownPlayer(number, name.to_string()))
+    }
+
+    fn amount(&self, number: usize, amount: &str) -> Result<u32, HistoryParseError> {
+        parse_amount(number, amount, self.cents)
+    }
+
+    fn parse_post(
+        &mut self,
+        number: usize,
+        player: PlayerId,
+        post: &str,
+    ) -> Result<(), HistoryParseError> {
+        let post = post.trim_end_matches(" and is all-in");
+        let amount = match post.split_whitespace().last() {
+            Some(amount) if post.starts_with("posts ") => self.amount(number, amount)?,
+            _ => return Ok(()),
+        };
+        if post.starts_with("posts the ante") {
+            self.history.antes.push((player, amount));
+            if self.history.blinds.ante.is_none() {
+                self.history.blinds.ante = Some(amount);
+            }
+            return Ok(());
+        }
+
+        if post.starts_with("posts small blind") {
+            self.history.small_blind = player;
+        } else if post.starts_with("posts big blind") {
+            self.history.big_blind = player;
+        }
+        // Only a big blind's worth of a dead small and big blind is live
+        let live = amount.min(self.history.blinds.bb);
+        *self.street_committed.entry(player).or_insert(0) += live;
+        self.history.blinds_posted.push((player, amount));
+        Ok(())
+    }
+
+    fn parse_action_line(&mut self, number: usize, line: &str) -> Result<(), HistoryParseError> {
+        if let Some(rest) = line.strip_prefix("Dealt to ") {
+            let (name, cards) = match rest.find(" [") {
+                Some(i) => (&rest[..i], Some(rest[i + 2..].trim_end_matches(']'))),
+                None => (rest, None),
+            };
+            let player = self.player_named(number, name)?;
+            if let Some(cards) = cards {
+                self.history.hero = Some(player);
+                let cards = parse_cards(cards)
+                    .map_err(|_| HistoryParseError::InvalidCards(number, cards.to_string()))?;
+                self.history.hole_cards.push((player, cards));
+            }
+            return Ok(());
+        }
+        if line.starts_with("Uncalled bet (") {
+            let malformed = || HistoryParseError::MalformedLine(number, line.to_string());
+            let amount = between(line, "(", ")").ok_or_else(malformed)?;
+            let name = &line[line.find(" returned to ").ok_or_else(malformed)? + 13..];
+            self.uncalled = Some((
+                self.player_named(number, name)?,
+                self.amount(number, amount)?,
+            ));
+            return Ok(());
+        }
+        if let Some((player, rest)) = self.player_line(line) {
+            return self.parse_action(number, player, rest);
+        }
+        if let Some(i) = line.find(" collected ") {
+            if let Ok(player) = self.player_named(number, &line[..i]) {
+                return self.parse_collected(number, player, &line[i + 11..]);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_action(
+        &mut self,
+        number: usize,
+        player: PlayerId,
+        text: &str,
+    ) -> Result<(), HistoryParseError> {
+        let all_in = text.ends_with(" and is all-in");
+        let text = text.trim_end_matches(" and is all-in");
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let committed = self.street_committed.get(&player).cloned().unwrap_or(0);
+        let action = match (words.first().cloned(), words.len()) {
+            (Some("folds"), _) => PlayerAction::Fold,
+            (Some("checks"), 1) => PlayerAction::Check,
+            (Some("calls"), 2) => PlayerAction::Call(self.amount(number, words[1])?),
+            (Some("bets"), 2) => PlayerAction::Bet(committed + self.amount(number, words[1])?),
+            (Some("raises"), 4) if words[2] == "to" => {
+                self.amount(number, words[1])?;
+                PlayerAction::Raise(self.amount(number, words[3])?)
+            }
+            (Some("shows"), _) => return self.parse_shows(number, player, text),
+            (Some("checks"), _) | (Some("calls"), _) | (Some("bets"), _) | (Some("raises"), _) => {
+                return Err(HistoryParseError::MalformedLine(number, text.to_string()))
+            }
+            // mucks, doesn't show, sits out and the like
+            _ => return Ok(()),
+        };
+
+        let committed = self.street_committed.entry(player).or_insert(0);
+        match action {
+            PlayerAction::Call(n) => *committed += n,
+            PlayerAction::Bet(to) | PlayerAction::Raise(to) => *committed = to,
+            PlayerAction::Check | PlayerAction::Fold => (),
+        }
+        self.history.actions.push(RecordedAction {
+            street: self.street,
+            player,
+            action,
+            all_in,
+        });
+        Ok(())
+    }
+
+    // e.g. "shows [Ah Ad] (a pair of Aces)". Only a hand with a full board gets a rank.
+    fn parse_shows(
+        &mut self,
+        number: usize,
+        player: PlayerId,
+        text: &str,
+    ) -> Result<(), HistoryParseError> {
+        let cards = between(text, "[", "]")
+            .ok_or_else(|| HistoryParseError::MalformedLine(number, text.to_string()))?;
+        let cards = parse_cards(cards)
+            .map_err(|_| HistoryParseError::InvalidCards(number, cards.to_string()))?;
+        let mut all_cards: CardVec = self.history.board.clone();
+        all_cards.extend(cards.iter().cloned());
+        if (5..=7).contains(&all_cards.len())
+            && !self.history.shown.iter().any(|s| s.player == player)
+        {
+            self.history.shown.push(ShownHand {
+                player,
+                cards,
+                rank: evaluate(&all_cards).hand_rank(),
+            });
+        }
+        Ok(())
+    }
+
+    // e.g. "10 from side pot-2"
+    fn parse_collected(
+        &mut self,
+        number: usize,
+        player: PlayerId,
+        text: &str,
+    ) -> Result<(), HistoryParseError> {
+        let malformed = || HistoryParseError::MalformedLine(number, text.to_string());
+        let mut parts = text.splitn(2, " from ");
+        let amount = self.amount(number, parts.next().ok_or_else(malformed)?)?;
+        let pot = match parts.next().map(|p| p.trim()) {
+            Some("pot") | Some("main pot") => 0,
+            Some("side pot") => 1,
+            Some(pot) if pot.starts_with("side pot-") => {
+                pot[9..].parse().map_err(|_| malformed())?
+            }
+            _ => return Err(malformed()),
+        };
+        self.history.awards.push(PotAward {
+            pot,
+            player,
+            amount,
+        });
+        Ok(())
+    }
+
+    // The game hands back an uncalled bet as an award, so it goes back in with the rest
+    fn finish(mut self) -> Result<HandHistory, HistoryParseError> {
+        if self.section != Section::Summary {
+            return Err(HistoryParseError::Truncated(self.header_line));
+        }
+        // Without a size in the table line, the seats taken are all we know of
+        if self.history.table_size == 0 {
+            self.history.table_size = self.history.seats.len();
+        }
+        self.history.awards.sort_by_key(|a| a.pot);
+        if let Some((player, amount)) = self.uncalled {
+            let awards = &mut self.history.awards;
+            match awards.iter().rposition(|a| a.player == player) {
+                Some(i) => awards[i].amount += amount,
+                None => {
+                    let pot = awards.iter().map(|a| a.pot + 1).max().unwrap_or(0);
+                    awards.push(PotAward {
+                        pot,
+                        player,
+                        amount,
+                    });
+                }
+            }
+        }
+        Ok(self.history)
+    }
+}
+
+// Play money and tournament chips are whole numbers; money amounts like "$1.5" become cents
+fn parse_amount(number: usize, amount: &str, cents: bool) -> Result<u32, HistoryParseError> {
+    let invalid = || HistoryParseError::InvalidAmount(number, amount.to_string());
+    let digits: String = amount
+        .chars()
+        .filter(|&c| c != '$' && c != '€' && c != '£' && c != ',')
+        .collect();
+    if !cents {
+        return digits.parse().map_err(|_| invalid());
+    }
+    let mut parts = digits.splitn(2, '.');
+    let whole: u32 = parts.next().unwrap_or("").parse().map_err(|_| invalid())?;
+    let fraction = match parts.next() {
+        Some(f) if f.len() == 1 => f.parse::<u32>().map_err(|_| invalid())? * 10,
+        Some(f) if f.len() == 2 => f.parse::<u32>().map_err(|_| invalid())?,
+        Some(_) => return Err(invalid()),
+        None => 0,
+    };
+    whole
+        .checked_mul(100)
+        .and_then(|cents| cents.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gamestate::init_seeded_game_state;
+    use hand_rankings::HandRank;
+    use history::init_history_recorder;
+    use player::init_players;
+    use std::sync::mpsc;
+
+    const CASH_HANDS: &str = "\u{feff}PokerStars Hand #230000000001:  Hold'em No Limit ($0.05/$0.10 USD) - 2021/06/01 20:15:30 CET [2021/06/01 14:15:30 ET]
+Table 'Alcyone II' 6-max Seat #2 is the button
+Seat 1: hero ($10 in chips)
+Seat 2: villain one ($12.35 in chips)
+Seat 4: shorty ($2 in chips)
+Seat 6: away ($10 in chips) is sitting out
+shorty: posts small blind $0.05
+hero: posts big blind $0.10
+*** HOLE CARDS ***
+Dealt to hero [Ah Ad]
+villain one: raises $0.20 to $0.30
+shorty: raises $1.70 to $2 and is all-in
+hero: raises $8 to $10 and is all-in
+villain one: folds
+Uncalled bet ($8) returned to hero
+villain one said, \"gl\"
+*** FLOP *** [Kd 7s 2c]
+*** TURN *** [Kd 7s 2c] [9h]
+*** RIVER *** [Kd 7s 2c 9h] [3d]
+*** SHOW DOWN ***
+shorty: shows [Qs Qh] (a pair of Queens)
+hero: shows [Ah Ad] (a pair of Aces)
+hero collected $4.30 from pot
+*** SUMMARY ***
+Total pot $4.30 | Rake $0
+Board [Kd 7s 2c 9h 3d]
+Seat 1: hero (big blind) showed [Ah Ad] and won ($4.30) with a pair of Aces
+Seat 2: villain one (button) folded before Flop
+Seat 4: shorty (small blind) showed [Qs Qh] and lost with a pair of Queens
+
+
+
+PokerStars Hand #230000000002:  Hold'em No Limit ($0.05/$0.10 USD) - 2021/06/01 20:16:45 CET [2021/06/01 14:16:45 ET]
+Table 'Alcyone II' 6-max Seat #2 is the button
+Seat 1: hero ($14.30 in chips)
+Seat 2: villain one ($12.05 in chips)
+villain one: posts small blind $0.05
+hero: posts big blind $0.10
+*** HOLE CARDS ***
+Dealt to hero [7c 2d]
+villain one: calls $0.05
+hero: checks
+*** FLOP *** [Ts 8s 3h]
+hero: bets $0.10
+villain one: raises $0.30 to $0.40
+hero: folds
+Uncalled bet ($0.30) returned to villain one
+villain one collected $0.40 from pot
+villain one: doesn't show hand
+*** SUMMARY ***
+Total pot $0.40 | Rake $0
+Board [Ts 8s 3h]
+Seat 1: hero (big blind) folded on the Flop
+Seat 2: villain one (button) (small blind) collected ($0.40)
+";
+
+    #[test]
+    fn it_parses_pokerstars_cash_hands() {
+        let hands = parse_pokerstars(CASH_HANDS).unwrap();
+        assert_eq!(hands.len(), 2);
+
+        let hand = &hands[0];
+        assert_eq!(hand.hand_number, 230_000_000_001);
+        assert_eq!(hand.table_size, 6);
+        assert_eq!(hand.game_seed, None);
+        assert_eq!(hand.hero, Some(0));
+        assert_eq!(
+            Some(hand.started_at),
+            parse_timestamp("2021/06/01 20:15:30")
+        );
+        assert_eq!((hand.blinds.sb, hand.blinds.bb), (5, 10));
+        assert_eq!(hand.structure, BettingStructure::NoLimit);
+        let seats: Vec<(PlayerId, &str, u32)> = hand
+            .seats
+            .iter()
+            .map(|s| (s.id, s.name.as_str(), s.chips))
+            .collect();
+        assert_eq!(
+            seats,
+            vec![
+                (0, "hero", 1000),
+                (1, "villain one", 1235),
+                (3, "shorty", 200)
+            ]
+        );
+        assert_eq!((hand.button, hand.small_blind, hand.big_blind), (1, 3, 0));
+        let actions: Vec<(PlayerId, PlayerAction, bool)> = hand
+            .actions
+            .iter()
+            .map(|a| (a.player, a.action, a.all_in))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (1, PlayerAction::Raise(30), false),
+                (3, PlayerAction::Raise(200), true),
+                (0, PlayerAction::Raise(1000), true),
+                (1, PlayerAction::Fold, false),
+            ]
+        );
+        assert_eq!(hand.board, parse_cards("Kd7s2c9h3d").unwrap());
+        let ranks: Vec<(PlayerId, HandRank)> =
+            hand.shown.iter().map(|s| (s.player, s.rank)).collect();
+        assert_eq!(ranks, vec![(3, HandRank::Pair), (0, HandRank::Pair)]);
+        assert_eq!(hand.uncalled_bet(), Some((0, 800)));
+        assert_eq!(
+            hand.collected(),
+            vec![PotAward {
+                pot: 0,
+                player: 0,
+                amount: 430,
+            }]
+        );
+
+        let pots = hand.pots();
+        assert_eq!(pots.len(), 1);
+        assert_eq!(pots[0].chips, 430);
+        assert_eq!(pots[0].participants, [0, 3].iter().cloned().collect());
+        let players = hand.players();
+        assert_eq!(players[0].hole_cards, Some(parse_cards("AhAd").unwrap()));
+        assert!(!players[1].in_hand);
+        assert_eq!(players[2].seat, 3);
+        assert!(hand
+            .to_pokerstars()
+            .contains("Seat 4: shorty (200 in chips)"));
+
+        let hand = &hands[1];
+        assert_eq!((hand.button, hand.small_blind, hand.big_blind), (1, 1, 0));
+        assert_eq!(hand.actions[2].action, PlayerAction::Bet(10));
+        assert_eq!(hand.actions[2].street, Street::Flop);
+        assert_eq!(hand.uncalled_bet(), Some((1, 30)));
+        assert_eq!(hand.collected()[0].amount, 40);
+    }
+
+    #[test]
+    fn it_reads_back_a_hand_nobody_acted_in() {
+        let (sender, receiver) = mpsc::channel();
+        let mut game = init_seeded_game_state(init_players(2, 0, 100), init_blinds(5, 10, None), 4);
+        game.players[1].chips = 4;
+        game.add_observer(Box::new(init_history_recorder(sender, 1_600_000_000)));
+        game.play_hand();
+
+        let recorded = receiver.try_recv().unwrap();
+        assert!(recorded.actions.is_empty());
+        let text = recorded.to_pokerstars();
+        assert!(text.contains("Uncalled bet (6) returned to"));
+
+        let parsed = parse_pokerstars(&text).unwrap().remove(0);
+        assert_eq!(parsed.uncalled_bet(), recorded.uncalled_bet());
+        assert_eq!(parsed.to_pokerstars(), text);
+    }
+
+    #[test]
+    fn it_reads_back_recorded_histories() {
+        let (sender, receiver) = mpsc::channel();
+        let mut game =
+            init_seeded_game_state(init_players(5, 0, 100), init_blinds(1, 2, Some(1)), 13);
+        game.add_observer(Box::new(init_history_recorder(sender, 1_600_000_000)));
+        game.play();
+
+        let recorded: Vec<HandHistory> = receiver.try_iter().collect();
+        let text: String = recorded.iter().map(|h| h.to_pokerstars()).collect();
+        let parsed = parse_pokerstars(&text).unwrap();
+
+        assert_eq!(parsed.len(), recorded.len());
+        for (parsed, recorded) in parsed.iter().zip(&recorded) {
+            assert_eq!(parsed.game_seed, Some(13));
+            assert_eq!(parsed.hand_number, recorded.hand_number);
+            assert_eq!(parsed.board, recorded.board);
+            let winners = |history: &HandHistory| -> Vec<(String, u32)> {
+                history
+                    .collected()
+                    .iter()
+                    .map(|a| (history.name(a.player), a.amount))
+                    .collect()
+            };
+            assert_eq!(winners(parsed), winners(recorded));
+            assert_eq!(parsed.to_pokerstars(), recorded.to_pokerstars());
+        }
+    }
+
+    #[test]
+    fn it_reports_errors_with_line_numbers() {
+        assert_eq!(
+            parse_pokerstars("\nhello"),
+            Err(HistoryParseError::ExpectedHeader(2, "hello".to_string()))
+        );
+        assert_eq!(
+            parse_pokerstars("PokerStars Hand #5: Omaha Pot Limit ($0.05/$0.10 USD) - 2021/06/01"),
+            Err(HistoryParseError::UnsupportedGame(
+                1,
+                "Omaha Pot Limit ($0.05/$0.10 USD)".to_string()
+            ))
+        );
+
+        let with_line = |number: usize, replacement: &str| -> String {
+            CASH_HANDS
+                .lines()
+                .enumerate()
+                .map(|(i, line)| if i + 1 == number { replacement } else { line })
+                .collect::<Vec<&str>>()
+                .join("\n")
+        };
+        let error = parse_pokerstars(&with_line(11, "villain one: raises lots to $0.30"));
+        assert_eq!(
+            error,
+            Err(HistoryParseError::InvalidAmount(11, "lots".to_string()))
+        );
+        let error = parse_pokerstars(&with_line(13, "hero: raises $8 to $10.001"));
+        assert_eq!(
+            error,
+            Err(HistoryParseError::InvalidAmount(13, "$10.001".to_string()))
+        );
+        let error = parse_pokerstars(&with_line(10, "Dealt to nobody [Ah Ad]"));
+        assert_eq!(
+            error,
+            Err(HistoryParseError::UnknownPlayer(10, "nobody".to_string()))
+        );
+        let error = parse_pokerstars(&with_line(18, "*** TURN *** [Kd 7s 2c] [9x]"));
+        assert_eq!(error.unwrap_err().line(), 18);
+        let error = parse_pokerstars(&with_line(24, "")).unwrap_err();
+        assert_eq!(error, HistoryParseError::Truncated(1));
+        assert_eq!(error.to_string(), "Line 1: hand ends before its summary");
+        // Too many cents for a stack is an error rather than an overflow
+        let error = parse_pokerstars(&with_line(3, "Seat 1: hero ($99999999 in chips)"));
+        assert_eq!(
+            error,
+            Err(HistoryParseError::InvalidAmount(3, "$99999999".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_leaves_out_impossible_start_times() {
+        let header = CASH_HANDS.lines().next().unwrap();
+        let bad_date = CASH_HANDS.replacen(
+            &header[header.find(" - ").unwrap()..],
+            " - 2020/03/00 00:00:00 UTC",
+            1,
+        );
+        let hands = parse_pokerstars(&bad_date).unwrap();
+        assert_eq!(hands[0].started_at, 0);
+        assert_eq!(
+            Some(hands[1].started_at),
+            parse_timestamp("2021/06/01 20:16:45")
+        );
+    }
+}
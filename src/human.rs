@@ -0,0 +1,259 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Stdin, Stdout, Write};
+use std::mem;
+
+use card::cards_to_short_string;
+use player::PlayerAction;
+use strategy::{GameView, Strategy};
+
+// A seat driven by a person at a terminal (or any reader/writer pair, for tests)
+
+#[derive(Debug, Fail)]
+pub enum ActionParseError {
+    #[fail(display = "Unknown action: {}", _0)]
+    UnknownAction(String),
+    #[fail(display = "Not a valid amount: {}", _0)]
+    InvalidAmount(String),
+    #[fail(display = "You can't {} right now", _0)]
+    NotAllowed(String),
+    #[fail(display = "Amount must be between {} and {}", _0, _1)]
+    OutOfRange(u32, u32),
+}
+
+pub struct HumanStrategy<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R, W> fmt::Debug for HumanStrategy<R, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HumanStrategy")
+    }
+}
+
+impl<R: BufRead, W: Write> Strategy for HumanStrategy<R, W> {
+    fn decide(&mut self, view: &GameView) -> PlayerAction {
+        self.prompt(view).unwrap_or_else(|_| view.fallback_action())
+    }
+}
+
+impl<R: BufRead, W: Write> HumanStrategy<R, W> {
+    // Re-prompts until the input parses to a legal action. Running out of input gives up on the
+    // hand rather than hanging the game.
+    fn prompt(&mut self, view: &GameView) -> io::Result<PlayerAction> {
+        render_view(view, &mut self.output)?;
+        loop {
+            write!(self.output, "> ")?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                writeln!(self.output, "No more input, giving up the hand")?;
+                return Ok(view.fallback_action());
+            }
+            match parse_action(&line, view) {
+                Ok(action) => return Ok(action),
+                Err(e) => writeln!(self.output, "{}", e)?,
+            }
+        }
+    }
+}
+
+pub fn init_human_strategy<R: BufRead, W: Write>(input: R, output: W) -> HumanStrategy<R, W> {
+    HumanStrategy { input, output }
+}
+
+pub fn init_terminal_strategy() -> HumanStrategy<BufReader<Stdin>, Stdout> {
+    init_human_strategy(BufReader::new(io::stdin()), io::stdout())
+}
+
+// Accepts "check", "call", "fold", "bet 20", "raise 40" and "allin". Bets and raises are to a
+// street total anywhere in the view's bet_range; a bare "bet" or "raise" takes the minimum.
+pub fn parse_action(input: &str, view: &GameView) -> Result<PlayerAction, ActionParseError> {
+    let input = input.trim().to_lowercase();
+    let mut words = input.split_whitespace();
+    let command = words.next().unwrap_or("");
+    let amount = match words.next() {
+        Some(word) => Some(
+            word.parse::<u32>()
+                .map_err(|_| ActionParseError::InvalidAmount(word.to_string()))?,
+        ),
+        None => None,
+    };
+    let not_allowed = || ActionParseError::NotAllowed(command.to_string());
+    let offered = |action: PlayerAction| {
+        view.legal_actions
+            .iter()
+            .cloned()
+            .find(|a| mem::discriminant(a) == mem::discriminant(&action))
+    };
+    let sized = |_| -> Result<u32, ActionParseError> {
+        let range = view.bet_range.ok_or_else(not_allowed)?;
+        match amount {
+            None => Ok(range.min),
+            Some(n) if range.contains(n) => Ok(n),
+            Some(_) => Err(ActionParseError::OutOfRange(range.min, range.max)),
+        }
+    };
+
+    match command {
+        "check" | "x" if view.is_legal(PlayerAction::Check) => Ok(PlayerAction::Check),
+        "fold" | "f" if view.is_legal(PlayerAction::Fold) => Ok(PlayerAction::Fold),
+        "call" | "c" => offered(PlayerAction::Call(0)).ok_or_else(not_allowed),
+        "bet" | "b" => offered(PlayerAction::Bet(0))
+            .ok_or_else(not_allowed)
+            .and_then(sized)
+            .map(PlayerAction::Bet),
+        "raise" | "r" => offered(PlayerAction::Raise(0))
+            .ok_or_else(not_allowed)
+            .and_then(sized)
+            .map(PlayerAction::Raise),
+        "allin" | "all-in" => match (view.bet_range, offered(PlayerAction::Bet(0))) {
+            (Some(range), Some(_)) => Ok(PlayerAction::Bet(range.max)),
+            (Some(range), None) => Ok(PlayerAction::Raise(range.max)),
+            _ => parse_action("call", view),
+        },
+        "check" | "x" | "fold" | "f" => Err(not_allowed()),
+        _ => Err(ActionParseError::UnknownAction(input.to_string())),
+    }
+}
+
+pub fn render_view<W: Write>(view: &GameView, output: &mut W) -> io::Result<()> {
+    writeln!(output)?;
+    writeln!(output, "--- Your turn ({:?}) ---", view.street)?;
+    if let Some(ref hole_cards) = view.hole_cards {
+        writeln!(output, "Hole cards: {}", cards_to_short_string(hole_cards))?;
+    }
+    writeln!(output, "Board: {}", cards_to_short_string(&view.board))?;
+    write!(output, "Pot: {}", view.pot)?;
+    if let Some(bet) = view.current_bet {
+        write!(output, ", current bet: {}", bet)?;
+    }
+    writeln!(output)?;
+    for seat in &view.seats {
+        let status = if seat.all_in {
+            "all in"
+        } else if seat.in_hand {
+            "in hand"
+        } else {
+            "folded"
+        };
+        let you = if seat.id == view.player_id {
+            " (you)"
+        } else {
+            ""
+        };
+        writeln!(
+            output,
+            "  {}{}: {} chips, {} in this street, {}",
+            seat.name, you, seat.chips, seat.street_committed, status
+        )?;
+    }
+
+    let (min, max) = view.bet_range.map_or((0, 0), |r| (r.min, r.max));
+    let options: Vec<String> = view
+        .legal_actions
+        .iter()
+        .map(|action| match *action {
+            PlayerAction::Check => "check".to_string(),
+            PlayerAction::Fold => "fold".to_string(),
+            PlayerAction::Call(n) => format!("call {}", n),
+            PlayerAction::Bet(_) => format!("bet {}-{}", min, max),
+            PlayerAction::Raise(_) => format!("raise to {}-{}", min, max),
+        })
+        .collect();
+    writeln!(output, "Options: {}", options.join(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::parse_cards;
+    use gamestate::Street;
+    use std::io::Cursor;
+    use strategy::{BetRange, SeatView};
+
+    fn view(legal_actions: Vec<PlayerAction>) -> GameView {
+        GameView {
+            player_id: 0,
+            hole_cards: Some(parse_cards("AsKh").unwrap()),
+            board: parse_cards("7h8h2c").unwrap(),
+            street: Street::Flop,
+            pot: 30,
+            current_bet: Some(20),
+            to_call: 10,
+            big_blind: 10,
+            seats: vec![SeatView {
+                id: 0,
+                name: "Player 0".to_string(),
+                chips: 90,
+                committed: 10,
+                street_committed: 10,
+                in_hand: true,
+                all_in: false,
+            }],
+            legal_actions,
+            bet_range: Some(BetRange { min: 30, max: 100 }),
+        }
+    }
+
+    fn facing_bet() -> GameView {
+        view(vec![
+            PlayerAction::Call(10),
+            PlayerAction::Fold,
+            PlayerAction::Raise(30),
+        ])
+    }
+
+    #[test]
+    fn it_parses_legal_actions() {
+        let v = facing_bet();
+
+        assert_eq!(parse_action("call", &v).unwrap(), PlayerAction::Call(10));
+        assert_eq!(parse_action(" Fold\n", &v).unwrap(), PlayerAction::Fold);
+        assert_eq!(parse_action("raise", &v).unwrap(), PlayerAction::Raise(30));
+        assert_eq!(
+            parse_action("raise 45", &v).unwrap(),
+            PlayerAction::Raise(45)
+        );
+        assert_eq!(parse_action("allin", &v).unwrap(), PlayerAction::Raise(100));
+    }
+
+    #[test]
+    fn it_rejects_illegal_input() {
+        let v = facing_bet();
+
+        assert!(parse_action("check", &v).is_err());
+        assert!(parse_action("bet 20", &v).is_err());
+        assert!(parse_action("raise 25", &v).is_err());
+        assert!(parse_action("raise 500", &v).is_err());
+        assert!(parse_action("raise lots", &v).is_err());
+        assert!(parse_action("dance", &v).is_err());
+    }
+
+    #[test]
+    fn it_reprompts_until_the_input_is_legal() {
+        let input = Cursor::new("check\nraise 25\nraise 60\n");
+        let mut output = vec![];
+        let action = {
+            let mut human = init_human_strategy(input, &mut output);
+            human.decide(&facing_bet())
+        };
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(action, PlayerAction::Raise(60));
+        assert!(output.contains("Hole cards: As Kh"));
+        assert!(output.contains("Board: 7h 8h 2c"));
+        assert!(output.contains("call 10 | fold | raise to 30-100"));
+        assert!(output.contains("You can't check right now"));
+        assert!(output.contains("Amount must be between 30 and 100"));
+    }
+
+    #[test]
+    fn it_gives_up_when_input_runs_out() {
+        let mut output = vec![];
+        let mut human = init_human_strategy(Cursor::new(""), &mut output);
+
+        assert_eq!(human.decide(&facing_bet()), PlayerAction::Fold);
+    }
+}
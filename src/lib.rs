@@ -1,16 +1,39 @@
+// failure_derive expands to impls nested in consts, which newer rustc flags.
+#![allow(non_local_definitions)]
+
 extern crate failure;
 #[macro_use]
 extern crate failure_derive;
 extern crate itertools;
 extern crate rand;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 
 pub mod card;
+pub mod card_set;
+pub mod config;
 pub mod deck;
+pub mod equity;
+pub mod evaluator;
+pub mod event;
 pub mod hand_rankings;
 pub mod hand;
+pub mod history;
+pub mod history_parser;
+pub mod human;
 pub mod gamestate;
 pub mod game;
 pub mod player;
+pub mod range;
+pub mod range_equity;
+pub mod replay;
+pub mod session;
+pub mod strategy;
 
 // How to make this not pub?
 pub mod test_utils;
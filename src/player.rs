@@ -1,16 +1,35 @@
-use rand::{thread_rng, Rng};
 use card::CardVec;
-use hand::Hand;
+use human::init_terminal_strategy;
+use strategy::{init_random_strategy, Strategy};
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Player {
     pub id: PlayerId,
+    // Where the player sits at the table. Unlike their place in GameState::players, this stays
+    // put as others bust.
+    pub seat: usize,
     pub name: String,
     pub hole_cards: Option<CardVec>,
     pub chips: u32,
+    // Chips put into the pot over the whole hand, used to layer side pots
+    pub committed: u32,
+    // Chips put in on the current street, which is what bets and raises are measured against
+    pub street_committed: u32,
     pub last_action: Option<PlayerAction>,
+    // Cleared by every full bet or raise from someone else. Once set the player can't raise
+    // again until it's cleared, and the street can't close until it's set.
+    pub acted_since_full_raise: bool,
     pub in_hand: bool,
     pub all_in: bool,
+    // Strategies aren't saved; a loaded player gets a random one
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_strategy"))]
+    pub strategy: Box<dyn Strategy>,
+}
+
+#[cfg(feature = "serde")]
+fn default_strategy() -> Box<dyn Strategy> {
+    Box::new(init_random_strategy(0))
 }
 
 pub type PlayerVec = Vec<Player>;
@@ -21,49 +40,70 @@ impl Player {
     pub fn init_for_round(&mut self) {
         self.in_hand = true;
         self.last_action = None;
+        self.acted_since_full_raise = false;
         self.hole_cards = None;
         self.all_in = false;
-        println!(
-            "Player {} starting round with {} chips",
-            self.id, self.chips
-        );
+        self.committed = 0;
+        self.street_committed = 0;
     }
 
+    // Bets and raises are to a street total; calls are the chips added. Anything beyond the
+    // player's stack puts them all in for less.
     pub fn bet(&mut self, amount: u32) -> PlayerAction {
-        let bet = self.give_chips(amount);
+        let bet = self.put_in_to(amount);
         self.last_action = Some(PlayerAction::Bet(bet));
+        self.acted_since_full_raise = true;
         PlayerAction::Bet(bet)
     }
 
     pub fn call(&mut self, amount: u32) -> PlayerAction {
         let call = self.give_chips(amount);
         self.last_action = Some(PlayerAction::Call(call));
+        self.acted_since_full_raise = true;
         PlayerAction::Call(call)
     }
 
     pub fn raise(&mut self, amount: u32) -> PlayerAction {
-        let raise = self.give_chips(amount);
+        let raise = self.put_in_to(amount);
         self.last_action = Some(PlayerAction::Raise(raise));
+        self.acted_since_full_raise = true;
         PlayerAction::Raise(raise)
     }
 
+    fn put_in_to(&mut self, street_total: u32) -> u32 {
+        let amount = street_total.saturating_sub(self.street_committed);
+        self.give_chips(amount);
+        self.street_committed
+    }
+
     pub fn check(&mut self) -> PlayerAction {
         self.last_action = Some(PlayerAction::Check);
+        self.acted_since_full_raise = true;
         PlayerAction::Check
     }
 
     pub fn fold(&mut self) -> PlayerAction {
         self.last_action = Some(PlayerAction::Fold);
+        self.acted_since_full_raise = true;
         self.in_hand = false;
         PlayerAction::Fold
     }
 
-    pub fn give_blinds(&mut self, blind_amount: u32) -> PlayerAction {
-        self.bet(blind_amount)
+    // Posting a blind isn't acting: the big blind still gets its option
+    pub fn give_blinds(&mut self, blind_amount: u32) -> u32 {
+        self.give_chips(blind_amount)
+    }
+
+    // Antes aren't a betting action, so they leave last_action alone and don't count towards
+    // the bet to call
+    pub fn give_ante(&mut self, ante_amount: u32) -> u32 {
+        let ante = self.give_chips(ante_amount);
+        self.street_committed -= ante;
+        ante
     }
 
     pub fn give_chips(&mut self, amount: u32) -> u32 {
-        if amount >= self.chips {
+        let amount = if amount >= self.chips {
             let amount = self.chips;
             self.chips = 0;
             self.all_in = true;
@@ -71,51 +111,31 @@ impl Player {
         } else {
             self.chips -= amount;
             amount
-        }
+        };
+        self.committed += amount;
+        self.street_committed += amount;
+        amount
     }
 
     pub fn receive_chips(&mut self, amount: u32) {
         self.chips += amount;
     }
 
-    pub fn announce_action(&mut self, current_bet: Option<u32>, minbet: u32) -> PlayerAction {
-        let last_bet = self.get_last_bet_amount();
-
-        let mut allowed_actions = match current_bet {
-            Some(n) if n >= self.chips => vec![PlayerAction::Call(n), PlayerAction::Fold],
-            Some(n) if n > last_bet => vec![
-                PlayerAction::Call(n),
-                PlayerAction::Raise(n * 2),
-                PlayerAction::Fold,
-            ],
-            Some(n) if n == last_bet && last_bet > 0 => {
-                vec![PlayerAction::Raise(n * 2), PlayerAction::Check]
-            }
-            _ => vec![PlayerAction::Check, PlayerAction::Bet(minbet)],
-        };
-
-        //allowed_actions.push(PlayerAction::Fold);
-
-        match thread_rng().choose(&allowed_actions).unwrap() {
-            &PlayerAction::Check => self.check(),
-            &PlayerAction::Fold => self.fold(),
-            &PlayerAction::Bet(n) => self.bet(n),
-            &PlayerAction::Call(n) => self.call(n),
-            &PlayerAction::Raise(n) => self.raise(n),
-        }
-    }
-
-    fn get_last_bet_amount(&mut self) -> u32 {
-        match self.last_action {
-            Some(PlayerAction::Bet(n))
-            | Some(PlayerAction::Call(n))
-            | Some(PlayerAction::Raise(n)) => n,
-            _ => 0,
+    // Returns the action as it actually happened, which is smaller if it put the player all in
+    pub fn perform(&mut self, action: PlayerAction) -> PlayerAction {
+        match action {
+            PlayerAction::Check => self.check(),
+            PlayerAction::Fold => self.fold(),
+            PlayerAction::Bet(n) => self.bet(n),
+            PlayerAction::Call(n) => self.call(n),
+            PlayerAction::Raise(n) => self.raise(n),
         }
     }
 }
 
+// Bet and Raise carry the player's street total after the action, Call the chips added
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PlayerAction {
     Bet(u32),
     Call(u32),
@@ -125,24 +145,48 @@ pub enum PlayerAction {
 }
 
 pub fn init_player(id: u32, name: &str, chips: u32) -> Player {
+    init_player_with_strategy(
+        id,
+        name,
+        chips,
+        Box::new(init_random_strategy(u64::from(id))),
+    )
+}
+
+pub fn init_player_with_strategy(
+    id: u32,
+    name: &str,
+    chips: u32,
+    strategy: Box<dyn Strategy>,
+) -> Player {
     let name = name.to_string();
     Player {
         id,
+        seat: 0,
         name,
         hole_cards: None,
         chips,
+        committed: 0,
+        street_committed: 0,
         last_action: None,
+        acted_since_full_raise: false,
         in_hand: false,
         all_in: false,
+        strategy,
     }
 }
 
-pub fn init_players(num_players: u32, user_player: bool, chips: u32) -> PlayerVec {
+// The first `humans` seats are played from the terminal
+pub fn init_players(num_players: u32, humans: u32, chips: u32) -> PlayerVec {
     (0..num_players)
         .map(|i| {
             let mut name = "Player ".to_string();
             name.push_str(&i.to_string());
-            init_player(i, &name, chips)
+            if i < humans {
+                init_player_with_strategy(i, &name, chips, Box::new(init_terminal_strategy()))
+            } else {
+                init_player(i, &name, chips)
+            }
         })
         .collect()
 }
@@ -0,0 +1,405 @@
+use std::fmt;
+use std::str::FromStr;
+
+use card::{ranks, suits, Card, CardVec, Rank};
+use card_set::CardSet;
+
+// Hand ranges in the usual shorthand, e.g. "TT+, AQs+, KJo, 76s-54s, AhKh, A5s:0.5".
+//
+// A range expands to individual two card combos, each carrying a weight in (0, 1] for how often
+// the hand is played. Later entries override the weight of combos already in the range.
+
+#[derive(Debug, Fail)]
+pub enum RangeParseError {
+    #[fail(display = "Invalid hand in range: {}", _0)]
+    InvalidHand(String),
+    #[fail(display = "Invalid weight in range: {}", _0)]
+    InvalidWeight(String),
+    #[fail(display = "Range endpoints don't line up: {}", _0)]
+    MismatchedEndpoints(String),
+}
+
+// Two hole cards, stored highest card first so equal combos compare equal
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Combo(Card, Card);
+
+impl Combo {
+    pub fn new(a: Card, b: Card) -> Combo {
+        assert!(
+            a != b,
+            "A combo needs two different cards, got {:?} twice",
+            a
+        );
+        if (a.rank, a.suit.val()) > (b.rank, b.suit.val()) {
+            Combo(a, b)
+        } else {
+            Combo(b, a)
+        }
+    }
+
+    pub fn high(self) -> Card {
+        self.0
+    }
+
+    pub fn low(self) -> Card {
+        self.1
+    }
+
+    pub fn cards(self) -> CardVec {
+        vec![self.0, self.1]
+    }
+
+    pub fn card_set(self) -> CardSet {
+        let mut set = CardSet::empty();
+        set.insert(self.0);
+        set.insert(self.1);
+        set
+    }
+}
+
+impl fmt::Display for Combo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.0.to_short_string(),
+            self.1.to_short_string()
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WeightedCombo {
+    pub combo: Combo,
+    pub weight: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Range {
+    combos: Vec<WeightedCombo>,
+}
+
+impl Range {
+    pub fn new() -> Range {
+        Range { combos: vec![] }
+    }
+
+    pub fn combos(&self) -> &[WeightedCombo] {
+        &self.combos
+    }
+
+    // Adds a combo, or updates its weight if it's already in the range
+    pub fn insert(&mut self, combo: Combo, weight: f64) {
+        match self.combos.iter_mut().find(|wc| wc.combo == combo) {
+            Some(existing) => existing.weight = weight,
+            None => self.combos.push(WeightedCombo { combo, weight }),
+        }
+    }
+
+    pub fn contains(&self, combo: Combo) -> bool {
+        self.combos.iter().any(|wc| wc.combo == combo)
+    }
+
+    pub fn weight(&self, combo: Combo) -> Option<f64> {
+        self.combos
+            .iter()
+            .find(|wc| wc.combo == combo)
+            .map(|wc| wc.weight)
+    }
+
+    // Drops every combo that uses a card already known to be elsewhere (board, hero's hand, ...)
+    pub fn remove_blocked(&mut self, known: CardSet) {
+        self.combos
+            .retain(|wc| wc.combo.card_set().is_disjoint(known));
+    }
+
+    pub fn without_blocked(&self, known: CardSet) -> Range {
+        let mut range = self.clone();
+        range.remove_blocked(known);
+        range
+    }
+
+    pub fn combo_count(&self) -> usize {
+        self.combos.len()
+    }
+
+    // Combo count with each combo counted by its weight
+    pub fn weighted_count(&self) -> f64 {
+        self.combos.iter().map(|wc| wc.weight).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.combos.is_empty()
+    }
+}
+
+impl FromStr for Range {
+    type Err = RangeParseError;
+
+    fn from_str(range: &str) -> Result<Self, RangeParseError> {
+        parse_range(range)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Suitedness {
+    Pair,
+    Suited,
+    Offsuit,
+    Any,
+}
+
+// A hand like "AKs" or "77", before expanding into combos
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct HandClass {
+    high: Rank,
+    low: Rank,
+    suitedness: Suitedness,
+}
+
+impl HandClass {
+    fn combos(self) -> Vec<Combo> {
+        let mut combos = vec![];
+        for high_suit in &suits() {
+            for low_suit in &suits() {
+                let suited = high_suit == low_suit;
+                let include = match self.suitedness {
+                    Suitedness::Pair => high_suit.val() < low_suit.val(),
+                    Suitedness::Suited => suited,
+                    Suitedness::Offsuit => !suited,
+                    Suitedness::Any => true,
+                };
+                if include {
+                    combos.push(Combo::new(
+                        Card {
+                            rank: self.high,
+                            suit: *high_suit,
+                        },
+                        Card {
+                            rank: self.low,
+                            suit: *low_suit,
+                        },
+                    ));
+                }
+            }
+        }
+        combos
+    }
+
+    fn with_ranks(self, high: u32, low: u32) -> HandClass {
+        HandClass {
+            high: ranks()[high as usize],
+            low: ranks()[low as usize],
+            suitedness: self.suitedness,
+        }
+    }
+}
+
+pub fn parse_range(range: &str) -> Result<Range, RangeParseError> {
+    let mut parsed = Range::new();
+
+    for token in range.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let (hands, weight) = match token.find(':') {
+            Some(i) => (&token[..i], parse_weight(&token[i + 1..])?),
+            None => (token, 1.0),
+        };
+        for combo in parse_hands(hands.trim())? {
+            parsed.insert(combo, weight);
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn parse_weight(weight: &str) -> Result<f64, RangeParseError> {
+    match f64::from_str(weight.trim()) {
+        Ok(w) if w > 0.0 && w <= 1.0 => Ok(w),
+        _ => Err(RangeParseError::InvalidWeight(weight.to_string())),
+    }
+}
+
+fn parse_hands(token: &str) -> Result<Vec<Combo>, RangeParseError> {
+    if let Some(i) = token.find('-') {
+        let from = parse_class(&token[..i])?;
+        let to = parse_class(&token[i + 1..])?;
+        return expand_dash(from, to)
+            .ok_or_else(|| RangeParseError::MismatchedEndpoints(token.to_string()));
+    }
+    if let Some(class) = token.strip_suffix('+') {
+        return Ok(expand_plus(parse_class(class)?));
+    }
+    if token.chars().count() == 4 {
+        return parse_combo(token).map(|c| vec![c]);
+    }
+
+    parse_class(token).map(HandClass::combos)
+}
+
+fn parse_combo(token: &str) -> Result<Combo, RangeParseError> {
+    let invalid = || RangeParseError::InvalidHand(token.to_string());
+    let chars: Vec<char> = token.chars().collect();
+    let card = |cs: &[char]| Card::from_str(&cs.iter().collect::<String>()).map_err(|_| invalid());
+    let (a, b) = (card(&chars[..2])?, card(&chars[2..])?);
+    if a == b {
+        return Err(invalid());
+    }
+    Ok(Combo::new(a, b))
+}
+
+fn parse_class(token: &str) -> Result<HandClass, RangeParseError> {
+    let invalid = || RangeParseError::InvalidHand(token.to_string());
+    let chars: Vec<char> = token.trim().chars().collect();
+    if chars.len() != 2 && chars.len() != 3 {
+        return Err(invalid());
+    }
+    let rank = |c: char| Rank::from_str(&c.to_string()).map_err(|_| invalid());
+    let (first, second) = (rank(chars[0])?, rank(chars[1])?);
+    let (high, low) = if first >= second {
+        (first, second)
+    } else {
+        (second, first)
+    };
+
+    let suitedness = match (high == low, chars.get(2).map(|c| c.to_ascii_lowercase())) {
+        (true, None) => Suitedness::Pair,
+        (false, Some('s')) => Suitedness::Suited,
+        (false, Some('o')) => Suitedness::Offsuit,
+        (false, None) => Suitedness::Any,
+        _ => return Err(invalid()),
+    };
+
+    Ok(HandClass {
+        high,
+        low,
+        suitedness,
+    })
+}
+
+// "TT+" is every pair from tens up, "AQs+" raises the kicker up to just below the top card
+fn expand_plus(class: HandClass) -> Vec<Combo> {
+    let (high, low) = (class.high.val(), class.low.val());
+    if class.suitedness == Suitedness::Pair {
+        (low..=Rank::Ace.val())
+            .flat_map(|r| class.with_ranks(r, r).combos())
+            .collect()
+    } else {
+        (low..high)
+            .flat_map(|r| class.with_ranks(high, r).combos())
+            .collect()
+    }
+}
+
+// "TT-77" walks pairs, "A5s-A2s" walks the kicker, "76s-54s" slides both cards keeping the gap
+fn expand_dash(from: HandClass, to: HandClass) -> Option<Vec<Combo>> {
+    if from.suitedness != to.suitedness {
+        return None;
+    }
+    let (from_high, from_low) = (from.high.val(), from.low.val());
+    let (to_high, to_low) = (to.high.val(), to.low.val());
+
+    let classes: Vec<HandClass> = if from.suitedness == Suitedness::Pair {
+        (from_low.min(to_low)..=from_low.max(to_low))
+            .map(|r| from.with_ranks(r, r))
+            .collect()
+    } else if from_high == to_high {
+        (from_low.min(to_low)..=from_low.max(to_low))
+            .map(|r| from.with_ranks(from_high, r))
+            .collect()
+    } else if from_high - from_low == to_high - to_low {
+        let gap = from_high - from_low;
+        (from_low.min(to_low)..=from_low.max(to_low))
+            .map(|r| from.with_ranks(r + gap, r))
+            .collect()
+    } else {
+        return None;
+    };
+
+    Some(classes.into_iter().flat_map(HandClass::combos).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::card_from_str;
+
+    fn count(range: &str) -> usize {
+        parse_range(range).unwrap().combo_count()
+    }
+
+    #[test]
+    fn it_expands_single_hands() {
+        assert_eq!(count("TT"), 6);
+        assert_eq!(count("AKs"), 4);
+        assert_eq!(count("KJo"), 12);
+        assert_eq!(count("AK"), 16);
+        assert_eq!(count("AhKh"), 1);
+    }
+
+    #[test]
+    fn it_expands_plus_notation() {
+        assert_eq!(count("TT+"), 30);
+        assert_eq!(count("AQs+"), 8);
+        assert_eq!(count("KTo+"), 36);
+        assert_eq!(count("22+"), 78);
+    }
+
+    #[test]
+    fn it_expands_dash_notation() {
+        assert_eq!(count("76s-54s"), 12);
+        assert_eq!(count("54s-76s"), 12);
+        assert_eq!(count("A2s-A5s"), 16);
+        assert_eq!(count("99-77"), 18);
+    }
+
+    #[test]
+    fn it_parses_a_full_range() {
+        let range = parse_range("TT+, AQs+, KJo, 76s-54s, AhKh").unwrap();
+
+        // AhKh is already in AQs+
+        assert_eq!(range.combo_count(), 30 + 8 + 12 + 12);
+        let ak = Combo::new(card_from_str("A", "H"), card_from_str("K", "H"));
+        assert!(range.contains(ak));
+        assert_eq!(
+            ak,
+            Combo::new(card_from_str("K", "H"), card_from_str("A", "H"))
+        );
+    }
+
+    #[test]
+    fn it_weights_combos() {
+        let range = parse_range("AA, KK:0.5, AsAh:0.25").unwrap();
+        let aces = Combo::new(card_from_str("A", "S"), card_from_str("A", "H"));
+        let kings = Combo::new(card_from_str("K", "S"), card_from_str("K", "H"));
+
+        assert_eq!(range.combo_count(), 12);
+        assert_eq!(range.weight(aces), Some(0.25));
+        assert_eq!(range.weight(kings), Some(0.5));
+        assert!((range.weighted_count() - (5.0 + 0.25 + 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_removes_blocked_combos() {
+        let mut range = parse_range("AA, AKs").unwrap();
+        let known = CardSet::from_str("As 7c").unwrap();
+        range.remove_blocked(known);
+
+        // Three aces left make three pairs, and AKs loses the spade combo
+        assert_eq!(range.combo_count(), 3 + 3);
+        assert!(range
+            .combos()
+            .iter()
+            .all(|wc| wc.combo.card_set().is_disjoint(known)));
+    }
+
+    #[test]
+    fn it_rejects_bad_ranges() {
+        assert!(parse_range("AKx").is_err());
+        assert!(parse_range("AAs").is_err());
+        assert!(parse_range("ZZ").is_err());
+        assert!(parse_range("AhAh").is_err());
+        assert!(parse_range("AK:1.5").is_err());
+        assert!(parse_range("AK:0").is_err());
+        assert!(parse_range("AKs-QJo").is_err());
+        assert!(parse_range("AKs-T8s").is_err());
+    }
+}
@@ -0,0 +1,516 @@
+use rand::Rng;
+
+use card::Card;
+use card_set::CardSet;
+use deck::{init_deck_without, seeded_rng};
+use equity::{
+    for_each_runout, sample_runout, EquityError, MonteCarloConfig, BOARD_SIZE, DECK_SIZE, Z_95,
+};
+use evaluator::{evaluate_set, HandValue};
+use range::{Combo, Range};
+
+// Equity of a hero range (a single hand is just a one combo range) against one or more villain
+// ranges. Combos that share a card with each other, the board or the dead cards never meet, and
+// every surviving matchup counts in proportion to the product of its combos' weights.
+
+const BATCH_SIZE: u64 = 1000;
+// Consecutive rejected matchup draws before a Monte Carlo run gives up on the ranges
+const MAX_REJECTIONS: u32 = 100_000;
+
+#[derive(Clone, Debug)]
+pub enum EquityMethod {
+    // Every valid matchup against every remaining board; only practical once the flop is out or
+    // the ranges are narrow
+    Exact,
+    MonteCarlo(MonteCarloConfig),
+}
+
+// Weighted results: every field is a sum of matchup weights rather than a count of runouts
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RangeResult {
+    pub wins: f64,
+    pub ties: f64,
+    pub losses: f64,
+    pub shares: f64,
+    pub share_squares: f64,
+    pub weight: f64,
+    pub samples: u64,
+}
+
+impl RangeResult {
+    fn add(&mut self, share: f64, winners: usize, weight: f64) {
+        if share == 0.0 {
+            self.losses += weight;
+        } else if winners == 1 {
+            self.wins += weight;
+        } else {
+            self.ties += weight;
+        }
+        self.shares += share * weight;
+        self.share_squares += share * share * weight;
+        self.weight += weight;
+        self.samples += 1;
+    }
+
+    pub fn equity(&self) -> f64 {
+        if self.weight == 0.0 {
+            0.0
+        } else {
+            self.shares / self.weight
+        }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        if self.weight == 0.0 {
+            0.0
+        } else {
+            self.wins / self.weight
+        }
+    }
+
+    pub fn tie_rate(&self) -> f64 {
+        if self.weight == 0.0 {
+            0.0
+        } else {
+            self.ties / self.weight
+        }
+    }
+
+    // Only meaningful for Monte Carlo results, where samples are drawn in proportion to weight
+    pub fn std_error(&self) -> f64 {
+        let n = self.samples as f64;
+        if n < 2.0 {
+            return f64::INFINITY;
+        }
+        let mean = self.equity();
+        let variance = (self.share_squares / self.weight - mean * mean).max(0.0) * n / (n - 1.0);
+        (variance / n).sqrt()
+    }
+
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        let margin = z * self.std_error();
+        let equity = self.equity();
+        ((equity - margin).max(0.0), (equity + margin).min(1.0))
+    }
+}
+
+// Hero's head to head record with one specific villain combo
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComboMatchup {
+    pub combo: Combo,
+    pub result: RangeResult,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComboEquity {
+    pub combo: Combo,
+    // Hero's share of the whole (possibly multiway) pot holding this combo
+    pub result: RangeResult,
+    // versus[v]: how this combo fares against each of villain v's combos it met
+    pub versus: Vec<Vec<ComboMatchup>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeEquity {
+    pub hero: RangeResult,
+    pub villains: Vec<RangeResult>,
+    pub combos: Vec<ComboEquity>,
+    pub converged: bool,
+}
+
+impl RangeEquity {
+    pub fn hero_interval(&self) -> (f64, f64) {
+        self.hero.confidence_interval(Z_95)
+    }
+}
+
+struct Accumulator {
+    ranges: Vec<Range>,
+    players: Vec<RangeResult>,
+    // per hero combo
+    combos: Vec<RangeResult>,
+    // versus[hero combo][villain][villain combo]
+    versus: Vec<Vec<Vec<RangeResult>>>,
+}
+
+impl Accumulator {
+    fn new(ranges: Vec<Range>) -> Accumulator {
+        let hero_combos = ranges[0].combo_count();
+        Accumulator {
+            players: vec![RangeResult::default(); ranges.len()],
+            combos: vec![RangeResult::default(); hero_combos],
+            versus: (0..hero_combos)
+                .map(|_| {
+                    ranges[1..]
+                        .iter()
+                        .map(|r| vec![RangeResult::default(); r.combo_count()])
+                        .collect()
+                })
+                .collect(),
+            ranges,
+        }
+    }
+
+    // picks[p] is an index into player p's range, values[p] that combo's hand on this board
+    fn tally(&mut self, picks: &[usize], values: &[HandValue], weight: f64) {
+        let best = *values.iter().max().unwrap();
+        let winners = values.iter().filter(|v| **v == best).count();
+        let share = |v: HandValue| {
+            if v == best {
+                1.0 / winners as f64
+            } else {
+                0.0
+            }
+        };
+
+        for (player, value) in self.players.iter_mut().zip(values) {
+            player.add(share(*value), winners, weight);
+        }
+        self.combos[picks[0]].add(share(values[0]), winners, weight);
+
+        let hero = values[0];
+        for (v, (&pick, &value)) in picks[1..].iter().zip(&values[1..]).enumerate() {
+            let (share, winners) = if hero > value {
+                (1.0, 1)
+            } else if hero == value {
+                (0.5, 2)
+            } else {
+                (0.0, 1)
+            };
+            self.versus[picks[0]][v][pick].add(share, winners, weight);
+        }
+    }
+
+    fn converged(&self, target: Option<f64>) -> bool {
+        match target {
+            Some(target) => self.players.iter().all(|p| p.std_error() <= target),
+            None => false,
+        }
+    }
+
+    fn finish(self, converged: bool) -> RangeEquity {
+        let ranges = self.ranges;
+        let combos = ranges[0]
+            .combos()
+            .iter()
+            .zip(self.combos)
+            .zip(self.versus)
+            .map(|((wc, result), versus)| ComboEquity {
+                combo: wc.combo,
+                result,
+                versus: versus
+                    .into_iter()
+                    .zip(&ranges[1..])
+                    .map(|(results, range)| {
+                        range
+                            .combos()
+                            .iter()
+                            .zip(results)
+                            .filter(|(_, result)| result.samples > 0)
+                            .map(|(wc, result)| ComboMatchup {
+                                combo: wc.combo,
+                                result,
+                            })
+                            .collect()
+                    })
+                    .collect(),
+            })
+            .filter(|c| c.result.samples > 0)
+            .collect();
+
+        let mut players = self.players.into_iter();
+        RangeEquity {
+            hero: players.next().unwrap(),
+            villains: players.collect(),
+            combos,
+            converged,
+        }
+    }
+}
+
+pub fn range_equity(
+    hero: &Range,
+    villains: &[Range],
+    board: &[Card],
+    dead: &[Card],
+    method: &EquityMethod,
+) -> Result<RangeEquity, EquityError> {
+    if villains.is_empty() {
+        return Err(EquityError::NotEnoughHands(1));
+    }
+    if board.len() > BOARD_SIZE {
+        return Err(EquityError::TooManyBoardCards(board.len()));
+    }
+    let mut known = CardSet::empty();
+    for card in board.iter().chain(dead) {
+        if !known.insert(*card) {
+            return Err(EquityError::DuplicateCard(*card));
+        }
+    }
+    let needed = known.count() as usize + 2 * (villains.len() + 1) + BOARD_SIZE - board.len();
+    if needed > DECK_SIZE {
+        return Err(EquityError::NotEnoughCards(needed));
+    }
+
+    let mut ranges = vec![hero.without_blocked(known)];
+    ranges.extend(villains.iter().map(|r| r.without_blocked(known)));
+    if ranges.iter().any(Range::is_empty) {
+        return Err(EquityError::NoValidMatchups);
+    }
+
+    match *method {
+        EquityMethod::Exact => exact_range_equity(ranges, CardSet::from(board), known),
+        EquityMethod::MonteCarlo(ref config) => {
+            monte_carlo_range_equity(ranges, CardSet::from(board), known, config)
+        }
+    }
+}
+
+// Every set of mutually disjoint combos, one per range, with its combined weight
+fn matchups(ranges: &[Range]) -> Vec<(Vec<usize>, CardSet, f64)> {
+    let mut matchups = vec![(vec![], CardSet::empty(), 1.0)];
+    for range in ranges {
+        let mut extended = vec![];
+        for &(ref picks, cards, weight) in &matchups {
+            for (i, wc) in range.combos().iter().enumerate() {
+                let combo = wc.combo.card_set();
+                if combo.is_disjoint(cards) {
+                    let mut picks = picks.clone();
+                    picks.push(i);
+                    extended.push((picks, cards | combo, weight * wc.weight));
+                }
+            }
+        }
+        matchups = extended;
+    }
+    matchups
+}
+
+fn exact_range_equity(
+    ranges: Vec<Range>,
+    board: CardSet,
+    known: CardSet,
+) -> Result<RangeEquity, EquityError> {
+    let matchups = matchups(&ranges);
+    if matchups.is_empty() {
+        return Err(EquityError::NoValidMatchups);
+    }
+    let remaining = init_deck_without(known).cards;
+    let to_deal = BOARD_SIZE - board.count() as usize;
+    let mut acc = Accumulator::new(ranges);
+    let mut values = vec![];
+
+    for_each_runout(&remaining, to_deal, board, &mut |full_board| {
+        let runout = full_board - board;
+        for &(ref picks, cards, weight) in &matchups {
+            if !cards.is_disjoint(runout) {
+                continue;
+            }
+            values.clear();
+            for (p, &pick) in picks.iter().enumerate() {
+                let combo = acc.ranges[p].combos()[pick].combo.card_set();
+                values.push(evaluate_set(combo | full_board));
+            }
+            acc.tally(picks, &values, weight);
+        }
+    });
+
+    Ok(acc.finish(true))
+}
+
+fn monte_carlo_range_equity(
+    ranges: Vec<Range>,
+    board: CardSet,
+    known: CardSet,
+    config: &MonteCarloConfig,
+) -> Result<RangeEquity, EquityError> {
+    let mut rng = seeded_rng(config.seed);
+    let to_deal = BOARD_SIZE - board.count() as usize;
+    let mut acc = Accumulator::new(ranges);
+    let mut picks = vec![];
+    let mut values = vec![];
+    let mut trials = 0;
+
+    while trials < config.max_trials {
+        let batch = BATCH_SIZE.min(config.max_trials - trials);
+        for _ in 0..batch {
+            let cards = sample_matchup(&acc.ranges, &mut picks, &mut rng)?;
+            let mut deck = init_deck_without(known | cards).cards;
+            let full_board = sample_runout(&mut deck, to_deal, board, &mut rng);
+
+            values.clear();
+            for (p, &pick) in picks.iter().enumerate() {
+                let combo = acc.ranges[p].combos()[pick].combo.card_set();
+                values.push(evaluate_set(combo | full_board));
+            }
+            // Combos were drawn in proportion to their weights already
+            acc.tally(&picks, &values, 1.0);
+        }
+        trials += batch;
+
+        if acc.converged(config.target_std_error) {
+            return Ok(acc.finish(true));
+        }
+    }
+
+    Ok(acc.finish(false))
+}
+
+// Rejection samples one combo per range: uniformly, kept with probability equal to its weight,
+// and thrown out entirely if any two combos collide
+fn sample_matchup<R: Rng>(
+    ranges: &[Range],
+    picks: &mut Vec<usize>,
+    rng: &mut R,
+) -> Result<CardSet, EquityError> {
+    for _ in 0..MAX_REJECTIONS {
+        picks.clear();
+        let mut cards = CardSet::empty();
+        let valid = ranges.iter().all(|range| {
+            let i = rng.gen_range(0, range.combo_count());
+            let wc = range.combos()[i];
+            let combo = wc.combo.card_set();
+            picks.push(i);
+            let keep = combo.is_disjoint(cards) && rng.gen::<f64>() < wc.weight;
+            cards = cards | combo;
+            keep
+        });
+        if valid {
+            return Ok(cards);
+        }
+    }
+    Err(EquityError::NoValidMatchups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::{parse_cards, Rank};
+    use equity::exact_equity;
+    use range::parse_range;
+
+    fn range(range: &str) -> Range {
+        parse_range(range).unwrap()
+    }
+
+    #[test]
+    fn it_matches_hand_vs_hand_equity() {
+        let board = parse_cards("7h8h2c").unwrap();
+        let hands = vec![parse_cards("AhKh").unwrap(), parse_cards("QsQd").unwrap()];
+        let expected = exact_equity(&hands, &board, &[]).unwrap();
+        let result = range_equity(
+            &range("AhKh"),
+            &[range("QsQd")],
+            &board,
+            &[],
+            &EquityMethod::Exact,
+        )
+        .unwrap();
+
+        assert!((result.hero.equity() - expected.players[0].equity()).abs() < 1e-9);
+        assert!((result.villains[0].equity() - expected.players[1].equity()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_breaks_down_equity_by_combo() {
+        let board = parse_cards("Kd7c2s9h3h").unwrap();
+        let result = range_equity(
+            &range("AA"),
+            &[range("KK, QQ")],
+            &board,
+            &[],
+            &EquityMethod::Exact,
+        )
+        .unwrap();
+
+        // Three kings left make a set of kings every time, queens always lose
+        assert_eq!(result.combos.len(), 6);
+        for combo in &result.combos {
+            let versus = &combo.versus[0];
+            assert_eq!(versus.len(), 3 + 6);
+            for matchup in versus {
+                let expected = if matchup.combo.high().rank == Rank::King {
+                    0.0
+                } else {
+                    1.0
+                };
+                assert_eq!(matchup.result.equity(), expected);
+            }
+        }
+        assert!((result.hero.equity() - 6.0 / 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_weights_matchups() {
+        let board = parse_cards("Kd7c2s9h3h").unwrap();
+        let result = range_equity(
+            &range("AA"),
+            &[range("KK:0.5, QQ")],
+            &board,
+            &[],
+            &EquityMethod::Exact,
+        )
+        .unwrap();
+
+        // 3 kings combos at half weight vs 6 queens combos
+        assert!((result.hero.equity() - 6.0 / 7.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_handles_card_removal() {
+        let board = parse_cards("As7c2d").unwrap();
+        let result = range_equity(
+            &range("AA, KK"),
+            &[range("AK")],
+            &board,
+            &[],
+            &EquityMethod::Exact,
+        )
+        .unwrap();
+
+        // Only three aces left: AA has 3 combos, AK villain has 3 aces * 4 kings minus overlaps
+        assert_eq!(result.combos.len(), 3 + 6);
+        for combo in &result.combos {
+            for matchup in &combo.versus[0] {
+                assert!(combo.combo.card_set().is_disjoint(matchup.combo.card_set()));
+            }
+        }
+    }
+
+    #[test]
+    fn it_estimates_multiway_range_equity() {
+        let config = MonteCarloConfig {
+            max_trials: 20000,
+            target_std_error: Some(0.005),
+            seed: 11,
+        };
+        let result = range_equity(
+            &range("AA"),
+            &[range("KK"), range("QQ")],
+            &[],
+            &[],
+            &EquityMethod::MonteCarlo(config),
+        )
+        .unwrap();
+
+        let total = result.hero.equity() + result.villains.iter().map(|v| v.equity()).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-9);
+        // Aces are roughly a two to one favourite three ways
+        let (low, high) = result.hero_interval();
+        assert!(low > 0.6 && high < 0.75);
+    }
+
+    #[test]
+    fn it_rejects_impossible_ranges() {
+        let board = parse_cards("AsAhAd").unwrap();
+        let result = range_equity(
+            &range("AcKc"),
+            &[range("AA")],
+            &board,
+            &[],
+            &EquityMethod::Exact,
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,389 @@
+use std::sync::mpsc;
+
+use card::{Card, CardVec};
+use card_set::CardSet;
+use deck::{init_deck_without, init_stacked_deck};
+use event::GameEvent;
+use gamestate::{
+    init_seeded_game_state, GameState, IllegalAction, Street, MAX_PLAYERS, MIN_PLAYERS,
+};
+use history::HandHistory;
+use player::{PlayerAction, PlayerId};
+
+// Steps a GameState through actions that were already taken, either a whole session from its
+// seed or a single recorded hand with its cards stacked
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum ReplayError {
+    #[fail(
+        display = "Need between {} and {} players, the history has {}",
+        _0, _1, _2
+    )]
+    PlayerCount(usize, usize, usize),
+    #[fail(display = "{} is dealt more than once", _0)]
+    DuplicateCard(String),
+    #[fail(
+        display = "Replay put the button and blinds on {:?}, the history has {:?}",
+        _0, _1
+    )]
+    Positions(
+        (PlayerId, PlayerId, PlayerId),
+        (PlayerId, PlayerId, PlayerId),
+    ),
+    #[fail(
+        display = "Action {} is for player {} but player {} is to act",
+        _0, _1, _2
+    )]
+    OutOfTurn(usize, PlayerId, PlayerId),
+    #[fail(display = "Action {} ({:?}) is illegal: {}", _0, _1, _2)]
+    Illegal(usize, PlayerAction, IllegalAction),
+    #[fail(display = "Game ended with {} actions left to replay", _0)]
+    GameOver(usize),
+}
+
+#[derive(Debug)]
+pub struct Replay {
+    pub game: GameState,
+    // Actions from a history say who they're for, so a replay that goes astray is caught
+    actions: Vec<(Option<PlayerId>, PlayerAction)>,
+    next_action: usize,
+}
+
+// Replays a session from the start. The game has to be set up the way the original was, with
+// the same players, blinds and seed; actions are everything the players did, in order.
+pub fn init_session_replay(game: GameState, actions: Vec<PlayerAction>) -> Replay {
+    Replay {
+        game,
+        actions: actions.into_iter().map(|a| (None, a)).collect(),
+        next_action: 0,
+    }
+}
+
+// Sets up the table as the history found it and deals its cards. Anyone whose hole cards
+// weren't recorded gets cards from the rest of the deck.
+pub fn init_replay(history: &HandHistory) -> Result<Replay, ReplayError> {
+    let seats = history.seats.len();
+    if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&seats) {
+        return Err(ReplayError::PlayerCount(MIN_PLAYERS, MAX_PLAYERS, seats));
+    }
+    let positions = (history.button, history.small_blind, history.big_blind);
+    let button = history
+        .seats
+        .iter()
+        .position(|s| s.id == history.button)
+        .ok_or(ReplayError::Positions(positions, positions))?;
+
+    let seed = history.game_seed.unwrap_or(0);
+    let mut game = init_seeded_game_state(history.players(), history.blinds, seed);
+    // Seated where the history had them, rather than packed together
+    for (player, seat) in game.players.iter_mut().zip(&history.seats) {
+        player.seat = seat.seat;
+    }
+    game.structure = history.structure;
+    let last_seat = history.seats.iter().map(|s| s.seat + 1).max().unwrap_or(0);
+    game.table_size = history.table_size.max(last_seat);
+    game.next_deck = Some(init_stacked_deck(&stacked_cards(history)?));
+    // Hands played here count up from 1 and pick the hand's shuffle and random choices. Other
+    // sites' numbers are just ids.
+    if history.game_seed.is_some() {
+        game.hand_count = (history.hand_number.min(u64::from(u32::MAX)) as u32).saturating_sub(1);
+    }
+    game.hand_limit = Some(game.hand_count + 1);
+    // Rotating at the start of the hand moves it onto the history's button
+    game.button = (button + seats - 1) % seats;
+
+    let mut replay = Replay {
+        game,
+        actions: history
+            .actions
+            .iter()
+            .map(|a| (Some(a.player), a.action))
+            .collect(),
+        next_action: 0,
+    };
+    // Listen in on the deal to check the positions, since the hand may be over before anyone
+    // gets to act
+    let (sender, receiver) = mpsc::channel();
+    replay.game.add_observer(Box::new(sender));
+    replay.game.next_decision();
+    replay.game.observers.pop();
+    for event in receiver.try_iter() {
+        if let GameEvent::HandStarted {
+            button,
+            small_blind,
+            big_blind,
+            ..
+        } = event
+        {
+            if (button, small_blind, big_blind) != positions {
+                return Err(ReplayError::Positions(
+                    (button, small_blind, big_blind),
+                    positions,
+                ));
+            }
+        }
+    }
+    Ok(replay)
+}
+
+// Hole cards two at a time in seat order, then the board. Cards shown down count as dealt.
+fn stacked_cards(history: &HandHistory) -> Result<CardVec, ReplayError> {
+    let mut known: Vec<Card> = history
+        .hole_cards
+        .iter()
+        .flat_map(|(_, cards)| cards.iter().cloned())
+        .collect();
+    for shown in &history.shown {
+        if !history.hole_cards.iter().any(|&(id, _)| id == shown.player) {
+            known.extend(shown.cards.iter().cloned());
+        }
+    }
+    known.extend(history.board.iter().cloned());
+    let mut seen = CardSet::empty();
+    for card in &known {
+        if !seen.insert(*card) {
+            return Err(ReplayError::DuplicateCard(card.to_short_string()));
+        }
+    }
+
+    let mut spare = init_deck_without(seen).cards.into_iter();
+    let mut cards = vec![];
+    for seat in &history.seats {
+        let dealt = history.hole_cards.iter().find(|&&(id, _)| id == seat.id);
+        let shown = history.shown.iter().find(|s| s.player == seat.id);
+        match (dealt, shown) {
+            (Some((_, hole_cards)), _) => cards.extend(hole_cards.iter().cloned()),
+            (None, Some(shown)) => cards.extend(shown.cards.iter().cloned()),
+            (None, None) => cards.extend(spare.by_ref().take(2)),
+        }
+    }
+    cards.extend(history.board.iter().cloned());
+    Ok(cards)
+}
+
+impl Replay {
+    // Applies the next action and runs the game on to the decision after it, so the board and
+    // pots are up to date. Ok(None) once every action has been replayed.
+    pub fn step(&mut self) -> Result<Option<PlayerAction>, ReplayError> {
+        let index = self.next_action;
+        let (player, action) = match self.actions.get(index) {
+            Some(&next) => next,
+            None => {
+                self.game.next_decision();
+                return Ok(None);
+            }
+        };
+        let request = match self.game.next_decision() {
+            Some(request) => request,
+            None => return Err(ReplayError::GameOver(self.actions.len() - index)),
+        };
+        if let Some(player) = player {
+            if player != request.view.player_id {
+                return Err(ReplayError::OutOfTurn(
+                    index,
+                    player,
+                    request.view.player_id,
+                ));
+            }
+        }
+
+        let taken = self
+            .game
+            .submit(action)
+            .map_err(|e| ReplayError::Illegal(index, action, e))?;
+        self.next_action += 1;
+        self.game.next_decision();
+        Ok(Some(taken))
+    }
+
+    // Steps until the game reaches the street, returning whether it ever did
+    pub fn run_to_street(&mut self, street: Street) -> Result<bool, ReplayError> {
+        while self.game.street != street {
+            if self.step()?.is_none() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn run_to_end(&mut self) -> Result<(), ReplayError> {
+        while self.step()?.is_some() {}
+        Ok(())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_action >= self.actions.len()
+    }
+
+    pub fn stacks(&self) -> Vec<(PlayerId, u32)> {
+        self.game.players.iter().map(|p| (p.id, p.chips)).collect()
+    }
+
+    pub fn pot(&self) -> u32 {
+        self.game.pot_total()
+    }
+
+    pub fn board(&self) -> &CardVec {
+        &self.game.board
+    }
+
+    pub fn street(&self) -> Street {
+        self.game.street
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::parse_cards;
+    use gamestate::init_blinds;
+    use history::init_history_recorder;
+    use history_parser::parse_pokerstars;
+    use player::init_players;
+    use std::collections::HashMap;
+
+    const SHOWDOWN_HAND: &str =
+        "PokerStars Hand #7: Hold'em No Limit (5/10) - 2021/06/01 20:15:30 ET
+Table 'Home' 3-max Seat #1 is the button
+Seat 1: alice (500 in chips)
+Seat 2: bob (500 in chips)
+Seat 3: carol (500 in chips)
+bob: posts small blind 5
+carol: posts big blind 10
+*** HOLE CARDS ***
+Dealt to alice [As Ad]
+alice: raises 20 to 30
+bob: calls 25
+carol: folds
+*** FLOP *** [Kd 7s 2c]
+bob: checks
+alice: bets 40
+bob: calls 40
+*** TURN *** [Kd 7s 2c] [9h]
+bob: checks
+alice: checks
+*** RIVER *** [Kd 7s 2c 9h] [3d]
+bob: bets 100
+alice: calls 100
+*** SHOW DOWN ***
+bob: shows [Kh Qh] (a pair of Kings)
+alice: shows [As Ad] (a pair of Aces)
+alice collected 350 from pot
+*** SUMMARY ***
+Total pot 350 | Rake 0
+";
+
+    fn session(seed: u64) -> GameState {
+        init_seeded_game_state(init_players(4, 0, 100), init_blinds(1, 2, Some(1)), seed)
+    }
+
+    #[test]
+    fn it_jumps_between_streets_of_a_hand() {
+        let history = &parse_pokerstars(SHOWDOWN_HAND).unwrap()[0];
+        let mut replay = init_replay(history).unwrap();
+        // Hand #7 is the other site's id, not a hand count to reseed from
+        assert_eq!(replay.game.hand_count, 1);
+        assert_eq!(replay.pot(), 15);
+        assert_eq!(
+            replay.game.players[0].hole_cards,
+            Some(parse_cards("AsAd").unwrap())
+        );
+
+        assert!(replay.run_to_street(Street::Flop).unwrap());
+        assert_eq!(replay.board(), &parse_cards("Kd7s2c").unwrap());
+        assert_eq!(replay.pot(), 70);
+        assert_eq!(replay.stacks(), vec![(0, 470), (1, 470), (2, 490)]);
+
+        assert!(replay.run_to_street(Street::River).unwrap());
+        assert_eq!(replay.board().len(), 5);
+        assert_eq!(replay.pot(), 150);
+
+        assert_eq!(replay.step(), Ok(Some(PlayerAction::Bet(100))));
+        replay.run_to_end().unwrap();
+        assert!(replay.is_done());
+        assert_eq!(replay.street(), Street::Showdown);
+        assert_eq!(replay.stacks(), vec![(0, 680), (1, 330), (2, 490)]);
+        assert!(!replay.run_to_street(Street::Flop).unwrap());
+    }
+
+    #[test]
+    fn it_replays_a_session_from_its_seed_and_actions() {
+        let (sender, receiver) = mpsc::channel();
+        let mut played = session(31);
+        played.add_observer(Box::new(sender));
+        played.play();
+        let actions: Vec<PlayerAction> = receiver
+            .try_iter()
+            .filter_map(|e| match e {
+                GameEvent::ActionTaken { action, .. } => Some(action),
+                _ => None,
+            })
+            .collect();
+
+        let mut replay = init_session_replay(session(31), actions);
+        replay.run_to_end().unwrap();
+
+        assert_eq!(replay.game.hand_count, played.hand_count);
+        assert_eq!(replay.stacks(), vec![(played.players[0].id, 400)]);
+    }
+
+    #[test]
+    fn it_replays_every_recorded_hand_of_a_session() {
+        let (sender, receiver) = mpsc::channel();
+        let (history_sender, histories) = mpsc::channel();
+        let mut played = session(32);
+        played.add_observer(Box::new(sender));
+        played.add_observer(Box::new(init_history_recorder(history_sender, 0)));
+        played.play();
+        let finished: HashMap<u32, Vec<(PlayerId, u32)>> = receiver
+            .try_iter()
+            .filter_map(|e| match e {
+                GameEvent::HandFinished {
+                    hand_number,
+                    stacks,
+                } => Some((hand_number, stacks)),
+                _ => None,
+            })
+            .collect();
+
+        for history in histories.try_iter() {
+            let mut replay = init_replay(&history).unwrap();
+            replay.run_to_end().unwrap();
+
+            let mut expected = finished[&(history.hand_number as u32)].clone();
+            expected.retain(|&(_, chips)| chips > 0);
+            assert_eq!(replay.stacks(), expected);
+            assert_eq!(replay.board(), &history.board);
+        }
+    }
+
+    #[test]
+    fn it_stops_at_actions_that_do_not_fit_the_hand() {
+        let mut history = parse_pokerstars(SHOWDOWN_HAND).unwrap().remove(0);
+        history.actions[1].player = 2;
+        let mut replay = init_replay(&history).unwrap();
+        assert_eq!(replay.step(), Ok(Some(PlayerAction::Raise(30))));
+        assert_eq!(replay.step(), Err(ReplayError::OutOfTurn(1, 2, 1)));
+
+        let mut history = parse_pokerstars(SHOWDOWN_HAND).unwrap().remove(0);
+        history.actions[0].action = PlayerAction::Check;
+        let mut replay = init_replay(&history).unwrap();
+        assert_eq!(
+            replay.step(),
+            Err(ReplayError::Illegal(
+                0,
+                PlayerAction::Check,
+                IllegalAction::CheckFacingBet(10)
+            ))
+        );
+
+        let mut history = parse_pokerstars(SHOWDOWN_HAND).unwrap().remove(0);
+        history.board = parse_cards("AsKd7s2c9h").unwrap();
+        assert_eq!(
+            init_replay(&history).unwrap_err(),
+            ReplayError::DuplicateCard("As".to_string())
+        );
+        history.seats.truncate(1);
+        assert!(init_replay(&history).is_err());
+    }
+}
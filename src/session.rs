@@ -0,0 +1,170 @@
+#[cfg(feature = "serde")]
+use serde_json;
+#[cfg(feature = "serde")]
+use std::fs::{self, File};
+use std::io;
+#[cfg(feature = "serde")]
+use std::io::{BufReader, BufWriter, Write};
+
+use gamestate::GameState;
+#[cfg(feature = "serde")]
+use human::init_terminal_strategy;
+use player::PlayerId;
+
+// A session saved between hands, so a long game can be stopped and picked up later. Everything
+// random in the next hand is reseeded from the game seed and hand count, so a resumed session
+// plays out exactly as it would have without the break. Saving needs the serde feature.
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Session {
+    pub game: GameState,
+    // Strategies aren't saved, so these seats go back to the terminal on load and everyone else
+    // gets a random strategy
+    pub humans: Vec<PlayerId>,
+}
+
+impl Session {
+    // Written to a scratch file first, so stopping mid-save leaves the last save intact
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        if self.game.hand_in_progress {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Sessions can only be saved between hands",
+            ));
+        }
+        let scratch = format!("{}.tmp", path);
+        {
+            let mut writer = BufWriter::new(File::create(&scratch)?);
+            serde_json::to_writer(&mut writer, self)?;
+            writer.flush()?;
+        }
+        fs::rename(scratch, path)
+    }
+
+    #[cfg(not(feature = "serde"))]
+    pub fn save(&self, _path: &str) -> io::Result<()> {
+        Err(serde_disabled())
+    }
+}
+
+pub fn init_session(game: GameState, humans: Vec<PlayerId>) -> Session {
+    Session { game, humans }
+}
+
+#[cfg(feature = "serde")]
+pub fn load_session(path: &str) -> io::Result<Session> {
+    let file = File::open(path)?;
+    let mut session: Session = serde_json::from_reader(BufReader::new(file))?;
+    if session.game.hand_in_progress {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Saved session stopped in the middle of a hand",
+        ));
+    }
+    for player in &mut session.game.players {
+        if session.humans.contains(&player.id) {
+            player.strategy = Box::new(init_terminal_strategy());
+        }
+    }
+    Ok(session)
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn load_session(_path: &str) -> io::Result<Session> {
+    Err(serde_disabled())
+}
+
+#[cfg(not(feature = "serde"))]
+fn serde_disabled() -> io::Error {
+    io::Error::other("Saving and resuming sessions needs pokers built with the serde feature")
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use gamestate::{init_blinds, init_seeded_game_state};
+    use player::{init_player, Player};
+    use std::env;
+    use std::process;
+
+    fn dummy_players(n: u32) -> Vec<Player> {
+        (0..n).map(|i| init_player(i, "Dummy", 100)).collect()
+    }
+
+    fn scratch_path(name: &str) -> String {
+        env::temp_dir()
+            .join(format!("pokers-{}-{}.json", name, process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn stacks(game: &GameState) -> Vec<(PlayerId, u32)> {
+        game.players.iter().map(|p| (p.id, p.chips)).collect()
+    }
+
+    #[test]
+    fn it_resumes_a_session_where_it_left_off() {
+        let path = scratch_path("resume");
+        let mut uninterrupted =
+            init_seeded_game_state(dummy_players(5), init_blinds(5, 10, None), 21);
+        uninterrupted.play();
+
+        let game = init_seeded_game_state(dummy_players(5), init_blinds(5, 10, None), 21);
+        let mut session = init_session(game, vec![]);
+        for _ in 0..4 {
+            assert!(session.game.play_hand());
+        }
+        session.save(&path).unwrap();
+        let mut resumed = load_session(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(resumed.game.hand_count, 4);
+        assert_eq!(resumed.game.seed, 21);
+        assert_eq!(resumed.game.button, session.game.button);
+        assert_eq!(stacks(&resumed.game), stacks(&session.game));
+
+        resumed.game.play();
+        assert_eq!(resumed.game.hand_count, uninterrupted.hand_count);
+        assert_eq!(stacks(&resumed.game), stacks(&uninterrupted));
+    }
+
+    #[test]
+    fn it_hands_human_seats_back_to_the_terminal() {
+        let path = scratch_path("humans");
+        let game = init_seeded_game_state(dummy_players(3), init_blinds(1, 2, None), 3);
+        init_session(game, vec![1]).save(&path).unwrap();
+        let session = load_session(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let strategies: Vec<String> = session
+            .game
+            .players
+            .iter()
+            .map(|p| format!("{:?}", p.strategy))
+            .collect();
+        assert!(strategies[1].starts_with("HumanStrategy"));
+        assert!(!strategies[0].starts_with("HumanStrategy"));
+        assert!(!strategies[2].starts_with("HumanStrategy"));
+    }
+
+    #[test]
+    fn it_only_saves_between_hands() {
+        let path = scratch_path("mid-hand");
+        let game = init_seeded_game_state(dummy_players(3), init_blinds(1, 2, None), 3);
+        let mut session = init_session(game, vec![]);
+        session.game.next_decision();
+        assert!(session.save(&path).is_err());
+
+        // A save that stopped mid-hand anyway, say from an older version, won't load
+        session.game.hand_in_progress = false;
+        let mut json = serde_json::to_value(&session).unwrap();
+        json["game"]["hand_in_progress"] = serde_json::Value::Bool(true);
+        fs::write(&path, json.to_string()).unwrap();
+        let error = load_session(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,222 @@
+use rand::Rng;
+use std::fmt;
+
+use card::CardVec;
+use deck::{seeded_rng, GameRng};
+use gamestate::{IllegalAction, Street};
+use player::{PlayerAction, PlayerId};
+
+// Everything a player is allowed to know when it's their turn to act
+#[derive(Clone, Debug)]
+pub struct GameView {
+    pub player_id: PlayerId,
+    pub hole_cards: Option<CardVec>,
+    pub board: CardVec,
+    pub street: Street,
+    // Chips across the main pot and all side pots
+    pub pot: u32,
+    pub current_bet: Option<u32>,
+    // Chips this player needs to add to call
+    pub to_call: u32,
+    pub big_blind: u32,
+    pub seats: Vec<SeatView>,
+    // The Bet or Raise in here is the smallest allowed; bet_range has the full sizing
+    pub legal_actions: Vec<PlayerAction>,
+    pub bet_range: Option<BetRange>,
+}
+
+// Street totals a bet or raise may be made to. max is the player's whole stack.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BetRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl BetRange {
+    pub fn contains(&self, amount: u32) -> bool {
+        amount >= self.min && amount <= self.max
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SeatView {
+    pub id: PlayerId,
+    pub name: String,
+    pub chips: u32,
+    pub committed: u32,
+    pub street_committed: u32,
+    pub in_hand: bool,
+    pub all_in: bool,
+}
+
+impl GameView {
+    pub fn seat(&self, id: PlayerId) -> Option<&SeatView> {
+        self.seats.iter().find(|s| s.id == id)
+    }
+
+    // What a player does when they can't or won't say: check if that's on offer, fold if not
+    pub fn fallback_action(&self) -> PlayerAction {
+        if self.legal_actions.contains(&PlayerAction::Check) {
+            PlayerAction::Check
+        } else {
+            PlayerAction::Fold
+        }
+    }
+
+    pub fn is_legal(&self, action: PlayerAction) -> bool {
+        self.validate(action).is_ok()
+    }
+
+    // Checks an action against what's on offer, saying why it isn't allowed. Bets and raises are
+    // legal at any size in bet_range.
+    pub fn validate(&self, action: PlayerAction) -> Result<(), IllegalAction> {
+        match action {
+            PlayerAction::Check if self.to_call > 0 => {
+                Err(IllegalAction::CheckFacingBet(self.to_call))
+            }
+            PlayerAction::Check | PlayerAction::Fold => Ok(()),
+            PlayerAction::Call(_) if self.to_call == 0 => Err(IllegalAction::NothingToCall),
+            PlayerAction::Call(n) if n != self.to_call => Err(IllegalAction::WrongCallAmount {
+                expected: self.to_call,
+                got: n,
+            }),
+            PlayerAction::Call(_) => Ok(()),
+            PlayerAction::Bet(_) if self.current_bet.is_some() => Err(IllegalAction::BetFacingBet),
+            PlayerAction::Raise(_) if self.current_bet.is_none() => {
+                Err(IllegalAction::RaiseWithoutBet)
+            }
+            PlayerAction::Bet(n) | PlayerAction::Raise(n) => match self.bet_range {
+                None => Err(IllegalAction::BettingClosed),
+                Some(range) if !range.contains(n) => Err(IllegalAction::SizeOutOfRange {
+                    amount: n,
+                    min: range.min,
+                    max: range.max,
+                }),
+                Some(_) => Ok(()),
+            },
+        }
+    }
+}
+
+// Decides what a seat does. Bots, scripted players and human input all sit behind this.
+pub trait Strategy: fmt::Debug {
+    fn decide(&mut self, view: &GameView) -> PlayerAction;
+
+    // Called before every hand with a seed derived from the game seed, so strategies that
+    // randomise stay reproducible
+    fn new_hand(&mut self, _seed: u64) {}
+
+    // Told when the engine turns down an action, before being asked again
+    fn rejected(&mut self, _action: PlayerAction, _error: &IllegalAction) {}
+}
+
+// Picks uniformly among the legal actions
+#[derive(Debug)]
+pub struct RandomStrategy {
+    rng: GameRng,
+}
+
+impl Strategy for RandomStrategy {
+    fn decide(&mut self, view: &GameView) -> PlayerAction {
+        self.rng
+            .choose(&view.legal_actions)
+            .cloned()
+            .unwrap_or(PlayerAction::Fold)
+    }
+
+    fn new_hand(&mut self, seed: u64) {
+        self.rng = seeded_rng(seed);
+    }
+}
+
+pub fn init_random_strategy(seed: u64) -> RandomStrategy {
+    RandomStrategy {
+        rng: seeded_rng(seed),
+    }
+}
+
+// Never folds or puts in chips voluntarily: checks when it can, calls otherwise
+#[derive(Debug)]
+pub struct CallingStation;
+
+impl Strategy for CallingStation {
+    fn decide(&mut self, view: &GameView) -> PlayerAction {
+        view.legal_actions
+            .iter()
+            .cloned()
+            .find(|a| *a == PlayerAction::Check)
+            .or_else(|| {
+                view.legal_actions
+                    .iter()
+                    .cloned()
+                    .find(|a| matches!(*a, PlayerAction::Call(_)))
+            })
+            .unwrap_or(PlayerAction::Fold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(legal_actions: Vec<PlayerAction>) -> GameView {
+        GameView {
+            player_id: 0,
+            hole_cards: None,
+            board: vec![],
+            street: Street::PreFlop,
+            pot: 15,
+            current_bet: Some(10),
+            to_call: 10,
+            big_blind: 10,
+            seats: vec![],
+            legal_actions,
+            bet_range: Some(BetRange { min: 20, max: 100 }),
+        }
+    }
+
+    #[test]
+    fn it_picks_a_legal_random_action() {
+        let v = view(vec![
+            PlayerAction::Call(10),
+            PlayerAction::Raise(20),
+            PlayerAction::Fold,
+        ]);
+        let mut strategy = init_random_strategy(1);
+
+        for _ in 0..50 {
+            assert!(v.is_legal(strategy.decide(&v)));
+        }
+    }
+
+    #[test]
+    fn it_reseeds_random_strategies_each_hand() {
+        let v = view(vec![
+            PlayerAction::Call(10),
+            PlayerAction::Raise(20),
+            PlayerAction::Fold,
+        ]);
+        let mut a = init_random_strategy(1);
+        let mut b = init_random_strategy(2);
+        a.new_hand(7);
+        b.new_hand(7);
+
+        for _ in 0..20 {
+            assert_eq!(a.decide(&v), b.decide(&v));
+        }
+    }
+
+    #[test]
+    fn it_checks_or_calls_as_a_calling_station() {
+        let mut strategy = CallingStation;
+
+        assert_eq!(
+            strategy.decide(&view(vec![PlayerAction::Check, PlayerAction::Bet(10)])),
+            PlayerAction::Check
+        );
+        assert_eq!(
+            strategy.decide(&view(vec![PlayerAction::Call(10), PlayerAction::Fold])),
+            PlayerAction::Call(10)
+        );
+    }
+}